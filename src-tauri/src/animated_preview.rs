@@ -0,0 +1,100 @@
+// Short looping previews made of clips from across a video, for hover previews in the grid.
+
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+use tokio::process::Command;
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+use crate::{ffmpeg_failure, ffmpeg_paths, full_decode_timeout, output_with_timeout, thumbnail_cache};
+
+/// Upper limits that keep previews small; higher requested values are capped.
+const MAX_PREVIEW_FPS: u32 = 8;
+const MAX_PREVIEW_WIDTH: u32 = 240;
+
+/// Number of clips a preview is assembled from, evenly spread over the video.
+const PREVIEW_CLIPS: u32 = 5;
+
+/// Clips shorter than this would flicker, so short previews use fewer clips.
+const MIN_CLIP_SECONDS: f64 = 0.5;
+
+/// Start times of `clips` clips of `clip_length` seconds, each centered in its slice of a
+/// `duration`-second video.
+fn clip_starts(duration: f64, clips: u32, clip_length: f64) -> Vec<f64> {
+    let slice = duration / clips as f64;
+    (0..clips)
+        .map(|i| (slice * (i as f64 + 0.5) - clip_length / 2.0).clamp(0.0, (duration - clip_length).max(0.0)))
+        .collect()
+}
+
+/// Generates (or reuses) a looping preview of about `duration_seconds` made of clips sampled
+/// across `video_path`, at most `MAX_PREVIEW_FPS` and `MAX_PREVIEW_WIDTH` wide. It is an
+/// animated WebP when ffmpeg has a WebP encoder and a GIF otherwise. Previews live in the
+/// thumbnail cache. Returns the path.
+#[tauri::command]
+pub async fn generate_animated_preview(app: AppHandle, video_path: String, duration_seconds: f64, fps: u32, width: u32) -> Result<String, AppError> {
+    if !(duration_seconds.is_finite() && duration_seconds > 0.0) {
+        return Err(AppError::InvalidInput("duration_seconds must be positive".to_string()));
+    }
+    let fps = fps.clamp(1, MAX_PREVIEW_FPS);
+    let width = width.clamp(16, MAX_PREVIEW_WIDTH);
+
+    let encoders = crate::ffmpeg_listing("-encoders").await?;
+    let webp_encoder = ["libwebp_anim", "libwebp"].into_iter().find(|encoder| encoders.contains(*encoder));
+    let extension = if webp_encoder.is_some() { "webp" } else { "gif" };
+
+    let path = Path::new(&video_path);
+    let preview_path = crate::thumbnail_path_for(
+        path,
+        &format!("_preview_{}ms_{}fps_{}.{}", (duration_seconds * 1000.0).round() as u64, fps, width, extension),
+    )?;
+    let normalized = database::normalize_path(&preview_path.to_string_lossy());
+    if preview_path.exists() {
+        thumbnail_cache::touch(&app.state::<Database>().conn(), &normalized)?;
+        return Ok(normalized);
+    }
+
+    let video_duration = crate::source_duration(&app, &video_path).await?;
+    let length = duration_seconds.min(video_duration);
+    let clips = PREVIEW_CLIPS.min(((length / MIN_CLIP_SECONDS).floor() as u32).max(1));
+    let clip_length = length / clips as f64;
+
+    // One seeked input per clip, so only the sampled parts are decoded.
+    let mut command = Command::new(ffmpeg_paths::ffmpeg());
+    for start in clip_starts(video_duration, clips, clip_length) {
+        command
+            .args(["-ss", &format!("{:.3}", start), "-t", &format!("{:.3}", clip_length), "-i"])
+            .arg(path);
+    }
+    let mut filter: String = (0..clips)
+        .map(|i| format!("[{}:v]fps={},scale={}:-2:flags=lanczos,setsar=1[c{}];", i, fps, width, i))
+        .collect();
+    filter.extend((0..clips).map(|i| format!("[c{}]", i)));
+    filter.push_str(&format!("concat=n={}:v=1:a=0", clips));
+    match webp_encoder {
+        Some(encoder) => {
+            filter.push_str("[out]");
+            command.args(["-filter_complex", &filter, "-map", "[out]", "-c:v", encoder, "-q:v", "60"]);
+        }
+        // A palette built from the clip itself keeps GIF banding down.
+        None => {
+            filter.push_str(",split[a][b];[a]palettegen[palette];[b][palette]paletteuse[out]");
+            command.args(["-filter_complex", &filter, "-map", "[out]"]);
+        }
+    }
+    command.args(["-an", "-loop", "0", "-y"]).arg(&preview_path);
+    // Only the clips are decoded, `length` seconds in all.
+    let output = output_with_timeout(&mut command, "ffmpeg", full_decode_timeout(Some(length)))
+        .await
+        .inspect_err(|_| {
+            let _ = std::fs::remove_file(&preview_path);
+        })?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&preview_path);
+        return Err(AppError::FfmpegFailed(ffmpeg_failure("Preview generation failed", &String::from_utf8_lossy(&output.stderr))));
+    }
+
+    thumbnail_cache::touch(&app.state::<Database>().conn(), &normalized)?;
+    thumbnail_cache::enforce_limit_in_background(&app);
+    Ok(normalized)
+}
@@ -0,0 +1,171 @@
+// Audio track analysis for quality triage (clipping, near-silent recordings) and waveform
+// images for the player.
+
+use serde::Serialize;
+use std::path::Path;
+use tokio::process::Command;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+use crate::{ffmpeg_failure, full_decode_timeout, output_with_timeout, power, thumbnail_cache, DEFAULT_PROBE_TIMEOUT};
+
+/// A track whose loudest sample reaches this level (dBFS) may be clipped...
+const CLIPPING_MAX_VOLUME_DB: f64 = -0.1;
+
+/// ...and it is flagged once at least this many samples sit at full scale.
+const CLIPPING_MIN_SAMPLES: u64 = 100;
+
+/// Tracks with a mean level below this (dBFS) are flagged as abnormally quiet.
+const QUIET_MEAN_VOLUME_DB: f64 = -50.0;
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct AudioLevels {
+    /// `false` for files without an audio track; all other fields are then empty.
+    pub has_audio: bool,
+    /// Mean level in dBFS.
+    pub mean_volume_db: Option<f64>,
+    /// Peak level in dBFS; 0 is full scale.
+    pub max_volume_db: Option<f64>,
+    /// Number of samples at full scale.
+    pub full_scale_samples: u64,
+    pub clipping_detected: bool,
+    pub quiet_detected: bool,
+}
+
+/// Payload of the "audio-levels" event sent for each file of a folder sweep.
+#[derive(Debug, Serialize, Clone)]
+pub struct AudioLevelsResult {
+    pub video_id: String,
+    pub full_path: String,
+    pub levels: Option<AudioLevels>,
+    pub error: Option<String>,
+}
+
+async fn has_audio_stream(path: &Path) -> Result<bool, AppError> {
+    let mut probe = Command::new(crate::ffmpeg_paths::ffprobe());
+    probe.args(["-v", "error", "-select_streams", "a", "-show_entries", "stream=index", "-of", "csv=p=0"])
+        .arg(path);
+    let output = output_with_timeout(&mut probe, "ffprobe", DEFAULT_PROBE_TIMEOUT).await?;
+    if !output.status.success() {
+        return Err(AppError::FfprobeFailed(ffmpeg_failure("ffprobe failed", &String::from_utf8_lossy(&output.stderr))));
+    }
+    Ok(!String::from_utf8_lossy(&output.stdout).trim().is_empty())
+}
+
+/// Parses the summary `volumedetect` logs at the end of a run, e.g.
+/// `[Parsed_volumedetect_0 @ 0x...] max_volume: -0.3 dB` and `... histogram_0db: 42`.
+fn parse_volumedetect(stderr: &str) -> AudioLevels {
+    let mut levels = AudioLevels { has_audio: true, ..Default::default() };
+    for line in stderr.lines().filter(|line| line.contains("volumedetect")) {
+        let Some((_, stat)) = line.split_once("] ") else { continue };
+        let Some((key, value)) = stat.split_once(':') else { continue };
+        let value = value.trim().trim_end_matches("dB").trim();
+        match key.trim() {
+            "mean_volume" => levels.mean_volume_db = value.parse().ok(),
+            "max_volume" => levels.max_volume_db = value.parse().ok(),
+            "histogram_0db" => levels.full_scale_samples = value.parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+
+    levels.clipping_detected = levels.max_volume_db.is_some_and(|max| max >= CLIPPING_MAX_VOLUME_DB)
+        && levels.full_scale_samples >= CLIPPING_MIN_SAMPLES;
+    levels.quiet_detected = levels.mean_volume_db.is_some_and(|mean| mean < QUIET_MEAN_VOLUME_DB);
+    levels
+}
+
+/// Decodes the first audio track in full with `volumedetect`, so this takes about as long as the
+/// audio takes to decode. `duration` (seconds) bounds how long that may take.
+async fn measure_audio_levels(path: &Path, duration: Option<f64>) -> Result<AudioLevels, AppError> {
+    if !has_audio_stream(path).await? {
+        return Ok(AudioLevels::default());
+    }
+
+    let mut analysis = Command::new(crate::ffmpeg_paths::ffmpeg());
+    analysis.arg("-i")
+        .arg(path)
+        .args(["-map", "0:a:0", "-vn", "-af", "volumedetect", "-f", "null", "-"]);
+    let output = output_with_timeout(&mut analysis, "ffmpeg", full_decode_timeout(duration)).await?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Err(AppError::FfmpegFailed(ffmpeg_failure("Audio analysis failed", &stderr)));
+    }
+    Ok(parse_volumedetect(&stderr))
+}
+
+/// Measures the mean and peak level of a stored video's audio track and flags clipping or
+/// near-silence. Nothing is stored.
+#[tauri::command]
+pub async fn analyze_audio_levels(db: State<'_, Database>, video_id: String) -> Result<AudioLevels, AppError> {
+    let video = database::video_by_id(&db.conn(), &video_id)?;
+    measure_audio_levels(Path::new(&video.full_path), video.duration).await
+}
+
+/// Runs `analyze_audio_levels` over every video of a folder, sending each result as an
+/// "audio-levels" event (`AudioLevelsResult`) as soon as it is ready. Returns the number of
+/// videos analyzed.
+#[tauri::command]
+pub async fn analyze_folder_audio_levels(app: AppHandle, db: State<'_, Database>, folder_name: String) -> Result<u64, AppError> {
+    let videos = database::query_videos(
+        &db.conn(),
+        "WHERE folder_name = ?1 ORDER BY file_name",
+        [&folder_name],
+    )?;
+    let mut throttle = power::BatteryThrottle::new(power::battery_policy(&app));
+
+    for video in &videos {
+        let started = std::time::Instant::now();
+        let (levels, error) = match measure_audio_levels(Path::new(&video.full_path), video.duration).await {
+            Ok(levels) => (Some(levels), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let _ = app.emit("audio-levels", AudioLevelsResult {
+            video_id: video.id.clone(),
+            full_path: video.full_path.clone(),
+            levels,
+            error,
+        });
+        throttle.pace(started.elapsed(), || false).await;
+    }
+
+    Ok(videos.len() as u64)
+}
+
+/// Renders the first audio track of a stored video as a `width`x`height` PNG waveform (mixed to
+/// mono) and returns its path, or `None` if the file has no audio. Images are kept in the
+/// thumbnails directory, so they count towards the thumbnail cache limit and are reused until
+/// evicted or the file changes.
+#[tauri::command]
+pub async fn generate_waveform(app: AppHandle, db: State<'_, Database>, video_id: String, width: u32, height: u32) -> Result<Option<String>, AppError> {
+    if width == 0 || height == 0 {
+        return Err(AppError::InvalidInput("Waveform size must be positive".to_string()));
+    }
+    let video = database::video_by_id(&db.conn(), &video_id)?;
+    let path = Path::new(&video.full_path);
+
+    let waveform_path = crate::thumbnail_path_for(path, &format!("_waveform_{}x{}.png", width, height))?;
+    let normalized = database::normalize_path(&waveform_path.to_string_lossy());
+    if waveform_path.exists() {
+        thumbnail_cache::touch(&db.conn(), &normalized)?;
+        return Ok(Some(normalized));
+    }
+    if !has_audio_stream(path).await? {
+        return Ok(None);
+    }
+
+    let filter = format!("[0:a:0]aformat=channel_layouts=mono,showwavespic=s={}x{}", width, height);
+    let mut render = Command::new(crate::ffmpeg_paths::ffmpeg());
+    render.arg("-i")
+        .arg(path)
+        .args(["-filter_complex", &filter, "-frames:v", "1", "-y"])
+        .arg(&waveform_path);
+    let output = output_with_timeout(&mut render, "ffmpeg", full_decode_timeout(video.duration)).await?;
+    if !output.status.success() {
+        return Err(AppError::FfmpegFailed(ffmpeg_failure("Waveform generation failed", &String::from_utf8_lossy(&output.stderr))));
+    }
+
+    thumbnail_cache::touch(&db.conn(), &normalized)?;
+    thumbnail_cache::enforce_limit_in_background(&app);
+    Ok(Some(normalized))
+}
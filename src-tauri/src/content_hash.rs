@@ -0,0 +1,204 @@
+// Content hashes of video files, for recognizing copies regardless of name or location.
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, State, Window};
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+use crate::VideoMetadata;
+
+/// Bytes read per step while hashing.
+const HASH_CHUNK_SIZE: usize = 1 << 20;
+
+/// Minimum time between two "hash-progress" events for the same file.
+const HASH_PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Payload of the "hash-progress" event.
+#[derive(Debug, Serialize, Clone)]
+pub struct HashProgress {
+    pub video_id: String,
+    pub full_path: String,
+    pub bytes_hashed: u64,
+    pub total_bytes: u64,
+}
+
+/// SHA-256 of the whole file as `sha256:<hex>`. `on_progress` is called with the bytes hashed
+/// so far and the file size, at most every `HASH_PROGRESS_INTERVAL` and once at the end.
+fn hash_file(path: &Path, mut on_progress: impl FnMut(u64, u64)) -> Result<String, AppError> {
+    let mut file = std::fs::File::open(path)?;
+    let total_bytes = file.metadata()?.len();
+
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; HASH_CHUNK_SIZE];
+    let mut bytes_hashed: u64 = 0;
+    let mut last_report = Instant::now();
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        bytes_hashed += read as u64;
+        if last_report.elapsed() >= HASH_PROGRESS_INTERVAL {
+            on_progress(bytes_hashed, total_bytes);
+            last_report = Instant::now();
+        }
+    }
+    on_progress(bytes_hashed, total_bytes);
+
+    let hex: String = hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect();
+    Ok(format!("sha256:{}", hex))
+}
+
+/// SHA-256 of the whole file as `sha256:<hex>`, without progress reporting.
+pub fn full_hash(path: &Path) -> Result<String, AppError> {
+    hash_file(path, |_, _| ())
+}
+
+/// Bytes read from each end of the file for `quick_hash`.
+const QUICK_HASH_SAMPLE: u64 = 1 << 20;
+
+/// SHA-256 of the file size plus the first and last `QUICK_HASH_SAMPLE` bytes, as
+/// `quick:<hex>`. Cheap enough to compute while indexing; equal quick hashes mark duplicate
+/// candidates, which `content_hash` can confirm.
+pub fn quick_hash(path: &Path) -> Result<String, AppError> {
+    let mut file = std::fs::File::open(path)?;
+    let size = file.metadata()?.len();
+
+    let mut hasher = Sha256::new();
+    hasher.update(size.to_le_bytes());
+    let mut buffer = Vec::new();
+    (&mut file).take(QUICK_HASH_SAMPLE).read_to_end(&mut buffer)?;
+    hasher.update(&buffer);
+    // Small files are covered completely by the first sample.
+    if size > QUICK_HASH_SAMPLE {
+        buffer.clear();
+        file.seek(SeekFrom::Start(size.saturating_sub(QUICK_HASH_SAMPLE).max(QUICK_HASH_SAMPLE)))?;
+        file.take(QUICK_HASH_SAMPLE).read_to_end(&mut buffer)?;
+        hasher.update(&buffer);
+    }
+
+    let hex: String = hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect();
+    Ok(format!("quick:{}", hex))
+}
+
+/// Hashes the full contents of a stored video, stores the result as its `content_hash` and
+/// returns it. Reading a large file takes a while, so progress is sent to `window` as
+/// "hash-progress" events (`HashProgress`).
+#[tauri::command]
+pub async fn hash_video_content(window: Window, db: State<'_, Database>, video_id: String) -> Result<String, AppError> {
+    let video = database::video_by_id(&db.conn(), &video_id)?;
+
+    let full_path = video.full_path.clone();
+    let hash = tauri::async_runtime::spawn_blocking(move || {
+        hash_file(Path::new(&full_path), |bytes_hashed, total_bytes| {
+            let _ = window.emit("hash-progress", HashProgress {
+                video_id: video_id.clone(),
+                full_path: full_path.clone(),
+                bytes_hashed,
+                total_bytes,
+            });
+        })
+    }).await??;
+
+    db.conn().execute(
+        "UPDATE videos SET content_hash = ?1 WHERE full_path = ?2",
+        [&hash, &video.full_path],
+    )?;
+    Ok(hash)
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DeduplicatedVideo {
+    /// The best copy: highest resolution, then bitrate, then file size.
+    pub video: VideoMetadata,
+    /// Other videos of the folder with the same content hash.
+    pub duplicate_count: u64,
+}
+
+/// Sort key for picking which of several copies to show.
+fn quality_rank(video: &VideoMetadata) -> (u64, u64, u64) {
+    let pixels = video.width.unwrap_or(0) as u64 * video.height.unwrap_or(0) as u64;
+    (pixels, video.video_bitrate.or(video.total_bitrate).unwrap_or(0), video.file_size)
+}
+
+/// Lists the videos of a folder with copies folded together: one entry per content hash, by
+/// file name. Videos without a stored hash are all listed. Nothing is deleted.
+#[tauri::command]
+pub async fn get_folder_videos_deduplicated(db: State<'_, Database>, folder_name: String) -> Result<Vec<DeduplicatedVideo>, AppError> {
+    let videos = database::query_videos(
+        &db.conn(),
+        "WHERE folder_name = ?1 ORDER BY file_name",
+        [&folder_name],
+    )?;
+
+    let mut listing: Vec<DeduplicatedVideo> = Vec::new();
+    // Content hash -> index into `listing`.
+    let mut by_hash: HashMap<String, usize> = HashMap::new();
+    for video in videos {
+        let Some(hash) = video.content_hash.clone() else {
+            listing.push(DeduplicatedVideo { video, duplicate_count: 0 });
+            continue;
+        };
+        match by_hash.get(&hash) {
+            Some(&index) => {
+                let entry = &mut listing[index];
+                entry.duplicate_count += 1;
+                if quality_rank(&video) > quality_rank(&entry.video) {
+                    entry.video = video;
+                }
+            }
+            None => {
+                by_hash.insert(hash, listing.len());
+                listing.push(DeduplicatedVideo { video, duplicate_count: 0 });
+            }
+        }
+    }
+
+    listing.sort_by(|a, b| a.video.file_name.cmp(&b.video.file_name));
+    Ok(listing)
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DuplicateCluster {
+    pub quick_hash: String,
+    /// By full path.
+    pub videos: Vec<VideoMetadata>,
+    /// Whether every member also has the same full `content_hash`; otherwise the cluster is
+    /// only a candidate for manual review.
+    pub confirmed: bool,
+}
+
+/// Groups the library by `quick_hash` and returns the groups with more than one video.
+/// Nothing is deleted.
+#[tauri::command]
+pub async fn find_duplicates(db: State<'_, Database>) -> Result<Vec<DuplicateCluster>, AppError> {
+    let videos = database::query_videos(
+        &db.conn(),
+        "WHERE quick_hash IN (
+             SELECT quick_hash FROM videos WHERE quick_hash IS NOT NULL
+             GROUP BY quick_hash HAVING COUNT(*) > 1
+         )
+         ORDER BY quick_hash, full_path",
+        [],
+    )?;
+
+    let mut clusters: Vec<DuplicateCluster> = Vec::new();
+    for video in videos {
+        let Some(quick_hash) = video.quick_hash.clone() else { continue };
+        match clusters.last_mut() {
+            Some(cluster) if cluster.quick_hash == quick_hash => cluster.videos.push(video),
+            _ => clusters.push(DuplicateCluster { quick_hash, videos: vec![video], confirmed: false }),
+        }
+    }
+    for cluster in &mut clusters {
+        let first = cluster.videos[0].content_hash.as_deref();
+        cluster.confirmed = first.is_some() && cluster.videos.iter().all(|video| video.content_hash.as_deref() == first);
+    }
+    Ok(clusters)
+}
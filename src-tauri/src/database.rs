@@ -0,0 +1,685 @@
+// SQLite storage for the video library.
+//
+// The connection lives in Tauri managed state (`State<'_, Database>`) and is opened once in
+// `run()`'s setup hook. Commands lock it for the duration of a query; never hold the lock
+// across an `.await`.
+
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, Params, Row};
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::error::AppError;
+use crate::{ResolutionClass, VideoMetadata};
+
+pub struct Database(Mutex<Connection>);
+
+impl Database {
+    /// Opens (or creates) the database file at `path` and makes sure the schema exists.
+    pub fn open(path: &Path) -> Result<Self, AppError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)?;
+        // Tag links follow renames and deletions of their video through foreign keys.
+        conn.pragma_update(None, "foreign_keys", true)?;
+        init_schema(&conn)?;
+        Ok(Database(Mutex::new(conn)))
+    }
+
+    pub fn conn(&self) -> MutexGuard<'_, Connection> {
+        // A panic while holding the lock doesn't leave the connection itself in a bad state.
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Columns added to `videos` after the initial schema, as `(name, declaration)`.
+/// Existing databases are brought up to date with `ALTER TABLE` on open.
+const ADDED_VIDEO_COLUMNS: &[(&str, &str)] = &[
+    ("starts_with_keyframe", "INTEGER"),
+    ("rotation", "INTEGER"),
+    ("display_width", "INTEGER"),
+    ("display_height", "INTEGER"),
+    ("profile", "TEXT"),
+    ("level", "INTEGER"),
+    ("decode_difficulty", "INTEGER"),
+    ("media_creation_date", "INTEGER"),
+    ("encoder", "TEXT"),
+    ("device_model", "TEXT"),
+    ("motion_thumbnail_path", "TEXT"),
+    ("container", "TEXT"),
+    ("audio_codec", "TEXT"),
+    ("web_ready", "INTEGER"),
+    ("motion_type", "TEXT"),
+    ("metadata_source", "TEXT"),
+    ("content_hash", "TEXT"),
+    ("created_at", "INTEGER"),
+    ("updated_at", "INTEGER"),
+    ("audio_channels", "INTEGER"),
+    ("audio_sample_rate", "INTEGER"),
+    ("audio_track_count", "INTEGER"),
+    ("has_audio", "INTEGER NOT NULL DEFAULT 0"),
+    ("variable_frame_rate", "INTEGER NOT NULL DEFAULT 0"),
+    ("container_mismatch", "INTEGER"),
+    ("recorded_date", "INTEGER"),
+    ("quick_hash", "TEXT"),
+    ("playable", "INTEGER"),
+    ("rating", "INTEGER"),
+    ("is_favorite", "INTEGER NOT NULL DEFAULT 0"),
+    ("subtitle_tracks", "TEXT"),
+    ("aspect_ratio", "REAL"),
+    ("resolution_class", "TEXT"),
+    ("color_space", "TEXT"),
+    ("color_transfer", "TEXT"),
+    ("color_primaries", "TEXT"),
+    ("is_hdr", "INTEGER NOT NULL DEFAULT 0"),
+];
+
+pub fn init_schema(conn: &Connection) -> rusqlite::Result<()> {
+    create_tables(conn)?;
+    add_missing_columns(conn)?;
+    create_search_index(conn)?;
+    // Rows indexed before `recorded_date` was stored.
+    conn.execute_batch(
+        "UPDATE videos SET recorded_date = COALESCE(media_creation_date, creation_date)
+         WHERE recorded_date IS NULL",
+    )
+}
+
+fn add_missing_columns(conn: &Connection) -> rusqlite::Result<()> {
+    let existing = {
+        let mut stmt = conn.prepare("SELECT name FROM pragma_table_info('videos')")?;
+        let names = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        names.collect::<rusqlite::Result<std::collections::HashSet<_>>>()?
+    };
+    for (name, declaration) in ADDED_VIDEO_COLUMNS {
+        if !existing.contains(*name) {
+            conn.execute_batch(&format!("ALTER TABLE videos ADD COLUMN {} {}", name, declaration))?;
+        }
+    }
+    Ok(())
+}
+
+fn create_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS videos (
+            id TEXT NOT NULL,
+            folder_name TEXT NOT NULL,
+            full_path TEXT NOT NULL UNIQUE,
+            file_name TEXT NOT NULL,
+            file_size INTEGER NOT NULL,
+            creation_date INTEGER NOT NULL,
+            modified_date INTEGER NOT NULL,
+            duration REAL,
+            width INTEGER,
+            height INTEGER,
+            fps REAL,
+            codec TEXT,
+            thumbnail_path TEXT,
+            total_bitrate INTEGER,
+            video_bitrate INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_videos_folder_name ON videos(folder_name);
+        CREATE TABLE IF NOT EXISTS view_state (
+            folder_name TEXT PRIMARY KEY,
+            sort_field TEXT NOT NULL,
+            sort_direction TEXT NOT NULL,
+            view_mode TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE
+        );
+        CREATE TABLE IF NOT EXISTS video_tags (
+            video_path TEXT NOT NULL REFERENCES videos(full_path) ON UPDATE CASCADE ON DELETE CASCADE,
+            tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+            PRIMARY KEY (video_path, tag_id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_video_tags_tag_id ON video_tags(tag_id);
+        CREATE TABLE IF NOT EXISTS thumbnail_access (
+            path TEXT PRIMARY KEY,
+            accessed_at INTEGER NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS operations (
+            id INTEGER PRIMARY KEY,
+            label TEXT NOT NULL,
+            started_at INTEGER NOT NULL,
+            undone INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE TABLE IF NOT EXISTS operation_entries (
+            id INTEGER PRIMARY KEY,
+            operation_id INTEGER NOT NULL REFERENCES operations(id) ON DELETE CASCADE,
+            old_path TEXT NOT NULL,
+            new_path TEXT NOT NULL,
+            -- VideoMetadata as JSON, as it was before the change
+            old_row TEXT NOT NULL,
+            undone INTEGER NOT NULL DEFAULT 0
+        );",
+    )
+}
+
+/// Creates the full-text index over file and folder names, filled from the existing rows the
+/// first time. Triggers keep it in step with every insert, update and delete on `videos`. It
+/// refers to rows by rowid, so after a VACUUM (which may renumber them) run
+/// `INSERT INTO videos_fts(videos_fts) VALUES('rebuild')`.
+fn create_search_index(conn: &Connection) -> rusqlite::Result<()> {
+    let exists = conn.query_row(
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'videos_fts'",
+        [],
+        |_| Ok(()),
+    ).is_ok();
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS videos_fts USING fts5(
+            file_name, folder_name, content = 'videos', tokenize = 'unicode61 remove_diacritics 2'
+        );
+        CREATE TRIGGER IF NOT EXISTS videos_fts_insert AFTER INSERT ON videos BEGIN
+            INSERT INTO videos_fts(rowid, file_name, folder_name) VALUES (new.rowid, new.file_name, new.folder_name);
+        END;
+        CREATE TRIGGER IF NOT EXISTS videos_fts_delete AFTER DELETE ON videos BEGIN
+            INSERT INTO videos_fts(videos_fts, rowid, file_name, folder_name)
+            VALUES ('delete', old.rowid, old.file_name, old.folder_name);
+        END;
+        CREATE TRIGGER IF NOT EXISTS videos_fts_update AFTER UPDATE OF file_name, folder_name ON videos BEGIN
+            INSERT INTO videos_fts(videos_fts, rowid, file_name, folder_name)
+            VALUES ('delete', old.rowid, old.file_name, old.folder_name);
+            INSERT INTO videos_fts(rowid, file_name, folder_name) VALUES (new.rowid, new.file_name, new.folder_name);
+        END;",
+    )?;
+    if !exists {
+        conn.execute_batch("INSERT INTO videos_fts(videos_fts) VALUES('rebuild')")?;
+    }
+    Ok(())
+}
+
+/// Turns free text typed into a search box into an FTS5 query matching rows that contain every
+/// word, the last one as a prefix since it may still be being typed. Quoting each word keeps
+/// FTS syntax such as `-`, `:` or `AND` in file names literal. `None` when there are no words.
+pub fn search_query(text: &str) -> Option<String> {
+    let words: Vec<String> = text.split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect();
+    let last = words.last()?;
+    Some(format!("{} {}*", words[..words.len() - 1].join(" "), last).trim_start().to_string())
+}
+
+/// Columns read by `row_to_video`. Use as `SELECT {VIDEO_COLUMNS} FROM videos ...`.
+pub const VIDEO_COLUMNS: &str = "id, folder_name, full_path, file_name, file_size, creation_date, \
+    modified_date, duration, width, height, fps, codec, thumbnail_path, total_bitrate, video_bitrate, \
+    starts_with_keyframe, rotation, display_width, display_height, profile, level, \
+    decode_difficulty, media_creation_date, encoder, device_model, motion_thumbnail_path, \
+    container, audio_codec, web_ready, motion_type, metadata_source, content_hash, created_at, \
+    updated_at, audio_channels, audio_sample_rate, audio_track_count, has_audio, \
+    variable_frame_rate, container_mismatch, recorded_date, quick_hash, playable, rating, \
+    is_favorite, subtitle_tracks, aspect_ratio, resolution_class, color_space, color_transfer, \
+    color_primaries, is_hdr";
+
+pub fn row_to_video(row: &Row) -> rusqlite::Result<VideoMetadata> {
+    Ok(VideoMetadata {
+        id: row.get("id")?,
+        folder_name: row.get("folder_name")?,
+        full_path: row.get("full_path")?,
+        file_name: row.get("file_name")?,
+        file_size: row.get("file_size")?,
+        creation_date: row.get::<_, i64>("creation_date")?.to_string(),
+        modified_date: row.get::<_, i64>("modified_date")?.to_string(),
+        duration: row.get("duration")?,
+        width: row.get("width")?,
+        height: row.get("height")?,
+        fps: row.get("fps")?,
+        codec: row.get("codec")?,
+        thumbnail_path: row.get("thumbnail_path")?,
+        total_bitrate: row.get("total_bitrate")?,
+        video_bitrate: row.get("video_bitrate")?,
+        starts_with_keyframe: row.get("starts_with_keyframe")?,
+        rotation: row.get("rotation")?,
+        display_width: row.get("display_width")?,
+        display_height: row.get("display_height")?,
+        profile: row.get("profile")?,
+        level: row.get("level")?,
+        decode_difficulty: row.get("decode_difficulty")?,
+        media_creation_date: row.get::<_, Option<i64>>("media_creation_date")?.map(|millis| millis.to_string()),
+        encoder: row.get("encoder")?,
+        device_model: row.get("device_model")?,
+        motion_thumbnail_path: row.get("motion_thumbnail_path")?,
+        container: row.get("container")?,
+        audio_codec: row.get("audio_codec")?,
+        web_ready: row.get("web_ready")?,
+        motion_type: row.get("motion_type")?,
+        metadata_source: row.get("metadata_source")?,
+        content_hash: row.get("content_hash")?,
+        created_at: row.get::<_, Option<i64>>("created_at")?.map(|millis| millis.to_string()),
+        updated_at: row.get::<_, Option<i64>>("updated_at")?.map(|millis| millis.to_string()),
+        audio_channels: row.get("audio_channels")?,
+        audio_sample_rate: row.get("audio_sample_rate")?,
+        audio_track_count: row.get("audio_track_count")?,
+        has_audio: row.get("has_audio")?,
+        variable_frame_rate: row.get("variable_frame_rate")?,
+        container_mismatch: row.get("container_mismatch")?,
+        recorded_date: row.get::<_, Option<i64>>("recorded_date")?.map(|millis| millis.to_string()),
+        quick_hash: row.get("quick_hash")?,
+        playable: row.get("playable")?,
+        rating: row.get("rating")?,
+        is_favorite: row.get("is_favorite")?,
+        subtitle_tracks: row.get::<_, Option<String>>("subtitle_tracks")?
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+        aspect_ratio: row.get("aspect_ratio")?,
+        resolution_class: row.get::<_, Option<String>>("resolution_class")?
+            .and_then(|name| ResolutionClass::from_name(&name)),
+        color_space: row.get("color_space")?,
+        color_transfer: row.get("color_transfer")?,
+        color_primaries: row.get("color_primaries")?,
+        is_hdr: row.get("is_hdr")?,
+    })
+}
+
+/// Runs `SELECT {VIDEO_COLUMNS} FROM videos {clause}` and maps every row to `VideoMetadata`.
+/// `clause` holds the WHERE/ORDER BY/LIMIT part; values must be bound through `params`.
+pub fn query_videos<P: Params>(conn: &Connection, clause: &str, params: P) -> Result<Vec<VideoMetadata>, AppError> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM videos {}", VIDEO_COLUMNS, clause))?;
+    let videos = stmt.query_map(params, row_to_video)?;
+    Ok(videos.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Selects a subset of the library by stored metadata. Unset fields don't restrict the selection.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct VideoFilter {
+    pub folder_name: Option<String>,
+    /// Codec name as reported by ffprobe (e.g. "hevc", "h264"), compared case-insensitively.
+    pub codec: Option<String>,
+    /// Only videos whose full path starts with this directory or path.
+    pub path_prefix: Option<String>,
+    pub min_height: Option<u32>,
+    pub max_height: Option<u32>,
+    pub web_ready: Option<bool>,
+    /// Only videos rated at least this many stars; unrated videos are left out.
+    pub min_rating: Option<u8>,
+    pub favorites_only: bool,
+    pub resolution_class: Option<ResolutionClass>,
+    /// Bounds on the displayed width over height, e.g. a maximum below 1 for portrait videos or a
+    /// minimum of 2 for ultrawide ones.
+    pub min_aspect_ratio: Option<f64>,
+    pub max_aspect_ratio: Option<f64>,
+}
+
+impl VideoFilter {
+    /// Builds the matching `WHERE ...` clause and its parameters, for use with `query_videos`.
+    pub fn to_sql(&self) -> (String, Vec<Value>) {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        if let Some(folder_name) = &self.folder_name {
+            values.push(Value::Text(folder_name.clone()));
+            conditions.push(format!("folder_name = ?{}", values.len()));
+        }
+        if let Some(codec) = &self.codec {
+            values.push(Value::Text(codec.to_lowercase()));
+            conditions.push(format!("LOWER(codec) = ?{}", values.len()));
+        }
+        if let Some(prefix) = &self.path_prefix {
+            // substr rather than LIKE, so `%` and `_` in paths aren't wildcards.
+            let prefix = normalize_path(prefix);
+            values.push(Value::Integer(prefix.chars().count() as i64));
+            values.push(Value::Text(prefix));
+            conditions.push(format!("substr(full_path, 1, ?{}) = ?{}", values.len() - 1, values.len()));
+        }
+        if let Some(min_height) = self.min_height {
+            values.push(Value::Integer(min_height.into()));
+            conditions.push(format!("height >= ?{}", values.len()));
+        }
+        if let Some(max_height) = self.max_height {
+            values.push(Value::Integer(max_height.into()));
+            conditions.push(format!("height <= ?{}", values.len()));
+        }
+        if let Some(web_ready) = self.web_ready {
+            values.push(Value::Integer(web_ready.into()));
+            conditions.push(format!("web_ready = ?{}", values.len()));
+        }
+        if let Some(min_rating) = self.min_rating {
+            values.push(Value::Integer(min_rating.into()));
+            conditions.push(format!("rating >= ?{}", values.len()));
+        }
+        if self.favorites_only {
+            conditions.push("is_favorite = 1".to_string());
+        }
+        if let Some(class) = self.resolution_class {
+            values.push(Value::Text(class.as_str().to_string()));
+            conditions.push(format!("resolution_class = ?{}", values.len()));
+        }
+        if let Some(min_aspect_ratio) = self.min_aspect_ratio {
+            values.push(Value::Real(min_aspect_ratio));
+            conditions.push(format!("aspect_ratio >= ?{}", values.len()));
+        }
+        if let Some(max_aspect_ratio) = self.max_aspect_ratio {
+            values.push(Value::Real(max_aspect_ratio));
+            conditions.push(format!("aspect_ratio <= ?{}", values.len()));
+        }
+
+        if conditions.is_empty() {
+            (String::new(), values)
+        } else {
+            (format!("WHERE {}", conditions.join(" AND ")), values)
+        }
+    }
+}
+
+/// Columns a page of videos can be ordered by.
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub enum SortField {
+    FileName,
+    FileSize,
+    Duration,
+    ModifiedDate,
+    Rating,
+}
+
+impl SortField {
+    /// `ORDER BY` terms for this field. Videos without a value come last either way, ties are
+    /// broken by path so pages don't overlap.
+    pub fn order_by(self, descending: bool) -> String {
+        let (column, collation) = match self {
+            SortField::FileName => ("file_name", " COLLATE NOCASE"),
+            SortField::FileSize => ("file_size", ""),
+            SortField::Duration => ("duration", ""),
+            SortField::ModifiedDate => ("modified_date", ""),
+            SortField::Rating => ("rating", ""),
+        };
+        let direction = if descending { "DESC" } else { "ASC" };
+        format!("{0} IS NULL, {0}{1} {2}, full_path", column, collation, direction)
+    }
+}
+
+/// Looks up a single video by its `id`. IDs combine folder and file name, so in the rare case
+/// of two same-named folders the first row by path is returned.
+pub fn video_by_id(conn: &Connection, id: &str) -> Result<VideoMetadata, AppError> {
+    query_videos(conn, "WHERE id = ?1 ORDER BY full_path LIMIT 1", [id])?
+        .pop()
+        .ok_or_else(|| AppError::NotFound(format!("Video not found: {}", id)))
+}
+
+/// Reads a JSON-encoded value from the settings table.
+pub fn get_setting<T: serde::de::DeserializeOwned>(conn: &Connection, key: &str) -> Result<Option<T>, AppError> {
+    use rusqlite::OptionalExtension;
+
+    let value: Option<String> = conn
+        .query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| row.get(0))
+        .optional()?;
+    value.map(|v| serde_json::from_str(&v).map_err(|e| AppError::Other(e.to_string()))).transpose()
+}
+
+/// Stores `value` JSON-encoded under `key` in the settings table.
+pub fn set_setting<T: serde::Serialize>(conn: &Connection, key: &str, value: &T) -> Result<(), AppError> {
+    let value = serde_json::to_string(value).map_err(|e| AppError::Other(e.to_string()))?;
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        [key, &value],
+    )?;
+    Ok(())
+}
+
+/// Tags are compared trimmed and lowercased so "B-Roll " and "b-roll" are the same tag.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+/// Attaches the (already normalized) `tag` to the video at `full_path`, creating the tag if needed.
+/// Returns whether the video didn't have the tag yet.
+pub fn tag_video(conn: &Connection, full_path: &str, tag: &str) -> rusqlite::Result<bool> {
+    conn.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", [tag])?;
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO video_tags (video_path, tag_id)
+         SELECT ?1, id FROM tags WHERE name = ?2",
+        [full_path, tag],
+    )?;
+    Ok(inserted > 0)
+}
+
+/// Brings a path into the single spelling stored in the database, so the same file can't end up
+/// in two rows. On Windows separators become `\`, verbatim prefixes (`\\?\`) are stripped and
+/// the drive letter is uppercased; everywhere repeated and trailing separators are dropped.
+pub fn normalize_path(path: &str) -> String {
+    let (separator, path) = if cfg!(windows) {
+        let path = path.replace('/', "\\");
+        let path = if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+            format!(r"\\{}", rest)
+        } else if let Some(rest) = path.strip_prefix(r"\\?\") {
+            rest.to_string()
+        } else {
+            path
+        };
+        ('\\', path)
+    } else {
+        ('/', path.to_string())
+    };
+
+    // Keep the double separator that starts a UNC path, collapse all others.
+    let (prefix, rest) = match path.strip_prefix(&format!("{0}{0}", separator)) {
+        Some(rest) if cfg!(windows) => (format!("{0}{0}", separator), rest),
+        _ => (String::new(), path.as_str()),
+    };
+    let mut normalized = prefix;
+    for c in rest.chars() {
+        if c != separator || !normalized.ends_with(separator) {
+            normalized.push(c);
+        }
+    }
+
+    // "C:\" and "/" are roots, their separator isn't trailing.
+    let is_root = normalized.len() == 1 || (cfg!(windows) && normalized.len() == 3 && normalized.ends_with(":\\"));
+    if normalized.ends_with(separator) && !is_root {
+        normalized.pop();
+    }
+
+    if cfg!(windows) && normalized.as_bytes().get(1) == Some(&b':') {
+        normalized[..1].make_ascii_uppercase();
+    }
+    normalized
+}
+
+/// Dates travel as epoch-millis strings (see `format_timestamp`) but are stored as integers
+/// so they can be sorted and compared in SQL. ISO-8601 and RFC 2822 dates (see
+/// `TimestampFormat`) are converted back to millis.
+fn timestamp_column(value: &str) -> i64 {
+    value.parse()
+        .ok()
+        .or_else(|| chrono::DateTime::parse_from_rfc3339(value).ok().map(|time| time.timestamp_millis()))
+        .or_else(|| chrono::DateTime::parse_from_rfc2822(value).ok().map(|time| time.timestamp_millis()))
+        .unwrap_or(0)
+}
+
+/// Current time as epoch millis, for `created_at` and `updated_at`.
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+/// Inserts `video`, or updates the existing row with the same `full_path`. `created_at` is set
+/// to the current time on insert and kept on update, `updated_at` is set to it on both; the
+/// values in `video` are ignored. Updates leave what the user curated alone, so re-indexing a
+/// file keeps it: `rating` and `is_favorite` are only written on insert, the thumbnail paths
+/// only when `video` has one (freshly probed metadata doesn't), and tags live in `video_tags`.
+pub fn upsert_video(conn: &Connection, video: &VideoMetadata) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO videos (id, folder_name, full_path, file_name, file_size, creation_date,
+            modified_date, duration, width, height, fps, codec, thumbnail_path, total_bitrate,
+            video_bitrate, starts_with_keyframe, rotation, display_width, display_height,
+            profile, level, decode_difficulty, media_creation_date, encoder, device_model,
+            motion_thumbnail_path, container, audio_codec, web_ready, motion_type,
+            metadata_source, content_hash, created_at, updated_at, audio_channels,
+            audio_sample_rate, audio_track_count, has_audio, variable_frame_rate,
+            container_mismatch, recorded_date, quick_hash, playable, rating, is_favorite,
+            subtitle_tracks, aspect_ratio, resolution_class, color_space, color_transfer,
+            color_primaries, is_hdr)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17,
+            ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31, ?32, ?33, ?34,
+            ?35, ?36, ?37, ?38, ?39, ?40, ?41, ?42, ?43, ?44, ?45, ?46, ?47, ?48, ?49, ?50, ?51,
+            ?52)
+         ON CONFLICT(full_path) DO UPDATE SET
+            id = excluded.id,
+            folder_name = excluded.folder_name,
+            file_name = excluded.file_name,
+            file_size = excluded.file_size,
+            creation_date = excluded.creation_date,
+            modified_date = excluded.modified_date,
+            duration = excluded.duration,
+            width = excluded.width,
+            height = excluded.height,
+            fps = excluded.fps,
+            codec = excluded.codec,
+            thumbnail_path = COALESCE(excluded.thumbnail_path, thumbnail_path),
+            total_bitrate = excluded.total_bitrate,
+            video_bitrate = excluded.video_bitrate,
+            starts_with_keyframe = excluded.starts_with_keyframe,
+            rotation = excluded.rotation,
+            display_width = excluded.display_width,
+            display_height = excluded.display_height,
+            profile = excluded.profile,
+            level = excluded.level,
+            decode_difficulty = excluded.decode_difficulty,
+            media_creation_date = excluded.media_creation_date,
+            encoder = excluded.encoder,
+            device_model = excluded.device_model,
+            motion_thumbnail_path = COALESCE(excluded.motion_thumbnail_path, motion_thumbnail_path),
+            container = excluded.container,
+            audio_codec = excluded.audio_codec,
+            web_ready = excluded.web_ready,
+            motion_type = excluded.motion_type,
+            metadata_source = excluded.metadata_source,
+            content_hash = excluded.content_hash,
+            updated_at = excluded.updated_at,
+            audio_channels = excluded.audio_channels,
+            audio_sample_rate = excluded.audio_sample_rate,
+            audio_track_count = excluded.audio_track_count,
+            has_audio = excluded.has_audio,
+            variable_frame_rate = excluded.variable_frame_rate,
+            container_mismatch = excluded.container_mismatch,
+            recorded_date = excluded.recorded_date,
+            quick_hash = excluded.quick_hash,
+            playable = excluded.playable,
+            subtitle_tracks = excluded.subtitle_tracks,
+            aspect_ratio = excluded.aspect_ratio,
+            resolution_class = excluded.resolution_class,
+            color_space = excluded.color_space,
+            color_transfer = excluded.color_transfer,
+            color_primaries = excluded.color_primaries,
+            is_hdr = excluded.is_hdr",
+        params![
+            video.id,
+            video.folder_name,
+            normalize_path(&video.full_path),
+            video.file_name,
+            video.file_size,
+            timestamp_column(&video.creation_date),
+            timestamp_column(&video.modified_date),
+            video.duration,
+            video.width,
+            video.height,
+            video.fps,
+            video.codec,
+            video.thumbnail_path.as_deref().map(normalize_path),
+            video.total_bitrate,
+            video.video_bitrate,
+            video.starts_with_keyframe,
+            video.rotation,
+            video.display_width,
+            video.display_height,
+            video.profile,
+            video.level,
+            video.decode_difficulty,
+            video.media_creation_date.as_deref().map(timestamp_column),
+            video.encoder,
+            video.device_model,
+            video.motion_thumbnail_path,
+            video.container,
+            video.audio_codec,
+            video.web_ready,
+            video.motion_type,
+            video.metadata_source,
+            video.content_hash,
+            now_millis(),
+            now_millis(),
+            video.audio_channels,
+            video.audio_sample_rate,
+            video.audio_track_count,
+            video.has_audio,
+            video.variable_frame_rate,
+            video.container_mismatch,
+            video.recorded_date.as_deref().map(timestamp_column),
+            video.quick_hash,
+            video.playable,
+            video.rating,
+            video.is_favorite,
+            serde_json::to_string(&video.subtitle_tracks).ok(),
+            video.aspect_ratio,
+            video.resolution_class.map(ResolutionClass::as_str),
+            video.color_space,
+            video.color_transfer,
+            video.color_primaries,
+            video.is_hdr,
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn video(full_path: &str) -> VideoMetadata {
+        serde_json::from_value(serde_json::json!({
+            "id": "clips_a.mp4",
+            "folder_name": "clips",
+            "full_path": full_path,
+            "file_name": "a.mp4",
+            "file_size": 1024,
+            "creation_date": "1700000000000",
+            "modified_date": "1700000000000",
+            "has_audio": false,
+            "variable_frame_rate": false,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn upsert_keeps_curated_fields() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let path = normalize_path("/videos/clips/a.mp4");
+
+        let mut first = video(&path);
+        first.rating = Some(4);
+        first.is_favorite = true;
+        first.thumbnail_path = Some("/thumbs/a.jpg".to_string());
+        first.motion_thumbnail_path = Some("/thumbs/a.motion.jpg".to_string());
+        upsert_video(&conn, &first).unwrap();
+        tag_video(&conn, &path, "holiday").unwrap();
+
+        // Freshly probed metadata, as a re-crawl produces it.
+        let mut second = video(&path);
+        second.file_size = 2048;
+        upsert_video(&conn, &second).unwrap();
+
+        let stored = video_by_id(&conn, "clips_a.mp4").unwrap();
+        assert_eq!(stored.file_size, 2048);
+        assert_eq!(stored.rating, Some(4));
+        assert!(stored.is_favorite);
+        assert_eq!(stored.thumbnail_path.as_deref(), first.thumbnail_path.as_deref().map(normalize_path).as_deref());
+        assert_eq!(stored.motion_thumbnail_path, first.motion_thumbnail_path);
+        let tags: Vec<String> = conn
+            .prepare("SELECT tags.name FROM video_tags JOIN tags ON tags.id = video_tags.tag_id WHERE video_path = ?1")
+            .unwrap()
+            .query_map([&path], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(tags, ["holiday"]);
+    }
+}
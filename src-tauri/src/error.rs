@@ -0,0 +1,117 @@
+// Error type returned by the commands.
+//
+// Serialized as `{ "kind": "FileNotFound", "message": "..." }` so the frontend can switch on
+// `kind` instead of matching message text. Internal helpers that still return `String` convert
+// into `Other` through `?`.
+
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "kind", content = "message")]
+pub enum AppError {
+    FileNotFound(String),
+    PermissionDenied(String),
+    /// The ffmpeg binary could not be started; see `ffmpeg_paths::set_ffmpeg_paths`.
+    FfmpegMissing(String),
+    /// The ffprobe binary could not be started; see `ffmpeg_paths::set_ffmpeg_paths`.
+    FfprobeMissing(String),
+    /// ffprobe ran but could not read the file. The message ends with its stderr.
+    FfprobeFailed(String),
+    /// ffmpeg ran but failed. The message ends with its stderr.
+    FfmpegFailed(String),
+    /// An external tool or a network path did not answer in time.
+    Timeout(String),
+    /// The run was stopped on request.
+    Cancelled(String),
+    /// A video, folder, job or similar referenced by the request doesn't exist.
+    NotFound(String),
+    /// A parameter was rejected before anything was done.
+    InvalidInput(String),
+    Database(String),
+    Io(String),
+    Other(String),
+}
+
+impl AppError {
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::FileNotFound(message)
+            | AppError::PermissionDenied(message)
+            | AppError::FfmpegMissing(message)
+            | AppError::FfprobeMissing(message)
+            | AppError::FfprobeFailed(message)
+            | AppError::FfmpegFailed(message)
+            | AppError::Timeout(message)
+            | AppError::Cancelled(message)
+            | AppError::NotFound(message)
+            | AppError::InvalidInput(message)
+            | AppError::Database(message)
+            | AppError::Io(message)
+            | AppError::Other(message) => message,
+        }
+    }
+
+    /// Maps the error of starting an external `program` (ffmpeg, ffprobe, ...). A missing
+    /// binary gets its own kind so the UI can point at the path settings.
+    pub fn spawn(program: &str, error: std::io::Error) -> AppError {
+        if error.kind() != std::io::ErrorKind::NotFound {
+            return AppError::Io(format!("Failed to run {}: {}", program, error));
+        }
+        let message = format!("{} not found: {}", program, error);
+        match program {
+            "ffprobe" => AppError::FfprobeMissing(message),
+            "ffmpeg" => AppError::FfmpegMissing(message),
+            _ => AppError::Io(message),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<std::io::Error> for AppError {
+    fn from(error: std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::NotFound => AppError::FileNotFound(error.to_string()),
+            std::io::ErrorKind::PermissionDenied => AppError::PermissionDenied(error.to_string()),
+            std::io::ErrorKind::TimedOut => AppError::Timeout(error.to_string()),
+            _ => AppError::Io(error.to_string()),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(error: rusqlite::Error) -> Self {
+        AppError::Database(error.to_string())
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(error: tauri::Error) -> Self {
+        AppError::Other(error.to_string())
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Other(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Other(message.to_string())
+    }
+}
+
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.to_string()
+    }
+}
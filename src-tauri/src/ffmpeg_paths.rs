@@ -0,0 +1,108 @@
+// Locations of the ffprobe and ffmpeg binaries, for machines where they aren't on PATH.
+//
+// The overrides are kept in the settings table and loaded at startup. Call sites build their
+// commands with `Command::new(ffmpeg_paths::ffprobe())`, which is the bare name when unset.
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use std::sync::RwLock;
+use tauri::State;
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+
+/// Settings key holding the `FfmpegPaths` overrides.
+const FFMPEG_PATHS_KEY: &str = "ffmpeg_paths";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct FfmpegPaths {
+    ffprobe: Option<PathBuf>,
+    ffmpeg: Option<PathBuf>,
+}
+
+static PATHS: RwLock<FfmpegPaths> = RwLock::new(FfmpegPaths { ffprobe: None, ffmpeg: None });
+
+fn paths() -> FfmpegPaths {
+    PATHS.read().map(|paths| paths.clone()).unwrap_or_default()
+}
+
+/// The ffprobe program to run.
+pub fn ffprobe() -> PathBuf {
+    paths().ffprobe.unwrap_or_else(|| PathBuf::from("ffprobe"))
+}
+
+/// The ffmpeg program to run.
+pub fn ffmpeg() -> PathBuf {
+    paths().ffmpeg.unwrap_or_else(|| PathBuf::from("ffmpeg"))
+}
+
+/// Loads the stored overrides. A broken setting is ignored so startup doesn't fail over it.
+pub fn load(conn: &Connection) {
+    if let Ok(Some(stored)) = database::get_setting::<FfmpegPaths>(conn, FFMPEG_PATHS_KEY) {
+        if let Ok(mut paths) = PATHS.write() {
+            *paths = stored;
+        }
+    }
+}
+
+/// An override must name an existing file; empty strings clear it.
+fn override_path(name: &str, path: Option<String>) -> Result<Option<PathBuf>, AppError> {
+    match path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty()) {
+        Some(path) if Path::new(&path).is_file() => Ok(Some(PathBuf::from(path))),
+        Some(path) => Err(AppError::FileNotFound(format!("{} not found at {}", name, path))),
+        None => Ok(None),
+    }
+}
+
+/// Sets the ffprobe and ffmpeg binaries to use instead of the ones on PATH. `None` (or an empty
+/// string) goes back to the PATH lookup. The overrides are stored and survive restarts.
+#[tauri::command]
+pub async fn set_ffmpeg_paths(db: State<'_, Database>, ffprobe: Option<String>, ffmpeg: Option<String>) -> Result<(), AppError> {
+    let paths = FfmpegPaths {
+        ffprobe: override_path("ffprobe", ffprobe)?,
+        ffmpeg: override_path("ffmpeg", ffmpeg)?,
+    };
+    database::set_setting(&db.conn(), FFMPEG_PATHS_KEY, &paths)?;
+    *PATHS.write().map_err(|e| e.to_string())? = paths;
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FfmpegInfo {
+    pub ffprobe_path: String,
+    /// First line of `ffprobe -version`, e.g. "ffprobe version 6.1.1 ...".
+    pub ffprobe_version: String,
+    pub ffmpeg_path: String,
+    /// `None` when ffmpeg can't be run; thumbnails and transcodes will fail.
+    pub ffmpeg_version: Option<String>,
+}
+
+async fn version_line(program: &Path) -> Result<String, String> {
+    let run = Command::new(program).arg("-version").kill_on_drop(true).output();
+    let output = tokio::time::timeout(crate::DEFAULT_PROBE_TIMEOUT, run)
+        .await
+        .map_err(|_| format!("{} -version timed out", program.display()))?
+        .map_err(|e| format!("Cannot run {}: {}", program.display(), e))?;
+    if !output.status.success() {
+        return Err(format!("{} -version failed", program.display()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).lines().next().unwrap_or_default().trim().to_string())
+}
+
+/// Runs `ffprobe -version` (and `ffmpeg -version`) with the configured binaries, so the UI can
+/// warn before a crawl rather than failing on every file. Errors when ffprobe can't be run.
+#[tauri::command]
+pub async fn check_ffmpeg_available() -> Result<FfmpegInfo, AppError> {
+    let (ffprobe, ffmpeg) = (ffprobe(), ffmpeg());
+    let ffprobe_version = version_line(&ffprobe)
+        .await
+        .map_err(|e| AppError::FfprobeMissing(format!("{}. Install ffmpeg or set its location with set_ffmpeg_paths.", e)))?;
+    Ok(FfmpegInfo {
+        ffprobe_path: ffprobe.to_string_lossy().to_string(),
+        ffprobe_version,
+        ffmpeg_path: ffmpeg.to_string_lossy().to_string(),
+        ffmpeg_version: version_line(&ffmpeg).await.ok(),
+    })
+}
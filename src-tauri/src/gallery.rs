@@ -0,0 +1,173 @@
+// Self-contained HTML gallery export for sharing a read-only view of a folder.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tauri::State;
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+use crate::VideoMetadata;
+
+/// Escapes text for use in HTML element content and attribute values.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Percent-encodes a path for use in an `href`/`src`, keeping `/` as the separator.
+fn encode_url_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.replace('\\', "/").bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                encoded.push(byte as char)
+            }
+            _ => {
+                let _ = write!(encoded, "%{:02X}", byte);
+            }
+        }
+    }
+    encoded
+}
+
+/// Path of `target` relative to the directory `base`, or `None` when they share no common root
+/// (e.g. different drives), in which case an absolute `file:` URL has to be used.
+fn relative_path(base: &Path, target: &Path) -> Option<PathBuf> {
+    let base: Vec<Component> = base.components().collect();
+    let target: Vec<Component> = target.components().collect();
+    if base.first() != target.first() {
+        return None;
+    }
+
+    let common = base.iter().zip(&target).take_while(|(a, b)| a == b).count();
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for component in &target[common..] {
+        relative.push(component.as_os_str());
+    }
+    Some(relative)
+}
+
+fn video_href(output_dir: &Path, video_path: &Path) -> String {
+    match relative_path(output_dir, video_path) {
+        Some(relative) => encode_url_path(&relative.to_string_lossy()),
+        None => {
+            let absolute = video_path.to_string_lossy().replace('\\', "/");
+            match absolute.strip_prefix("//") {
+                // \\server\share\clip.mp4 -> file://server/share/clip.mp4
+                Some(unc) => format!("file://{}", encode_url_path(unc)),
+                // C:/clips/clip.mp4 -> file:///C:/clips/clip.mp4
+                None => format!("file:///{}", encode_url_path(absolute.trim_start_matches('/'))),
+            }
+        }
+    }
+}
+
+fn format_duration(seconds: f64) -> String {
+    let total = seconds.round() as u64;
+    if total >= 3600 {
+        format!("{}:{:02}:{:02}", total / 3600, total % 3600 / 60, total % 60)
+    } else {
+        format!("{}:{:02}", total / 60, total % 60)
+    }
+}
+
+fn video_details(video: &VideoMetadata) -> String {
+    let mut details = Vec::new();
+    if let (Some(width), Some(height)) = (video.width, video.height) {
+        details.push(format!("{}x{}", width, height));
+    }
+    if let Some(duration) = video.duration {
+        details.push(format_duration(duration));
+    }
+    if let Some(codec) = &video.codec {
+        details.push(codec.clone());
+    }
+    details.push(format!("{:.1} MB", video.file_size as f64 / (1024.0 * 1024.0)));
+    details.join(" · ")
+}
+
+const GALLERY_STYLE: &str = "body{font-family:system-ui,sans-serif;margin:0;padding:24px;background:#111;color:#eee}\
+h1{font-size:1.4em;margin:0 0 16px}\
+.grid{display:grid;grid-template-columns:repeat(auto-fill,minmax(240px,1fr));gap:16px}\
+.card{background:#1c1c1c;border-radius:8px;overflow:hidden;text-decoration:none;color:inherit}\
+.card img,.card .placeholder{display:block;width:100%;aspect-ratio:16/9;object-fit:cover;background:#333}\
+.card .name{padding:8px 10px 2px;font-size:.9em;word-break:break-all}\
+.card .details{padding:0 10px 10px;font-size:.75em;color:#999}";
+
+/// Writes `index.html` plus a `thumbnails/` directory into `output_dir` showing every video of
+/// `folder_name` in a grid. Videos are linked by relative path, so keep the export next to the
+/// library (or on the same drive) for the links to work. Returns the path of `index.html`.
+#[tauri::command]
+pub async fn export_html_gallery(db: State<'_, Database>, folder_name: String, output_dir: String) -> Result<String, AppError> {
+    let videos = database::query_videos(
+        &db.conn(),
+        "WHERE folder_name = ?1 ORDER BY file_name",
+        [&folder_name],
+    )?;
+
+    let output_dir = PathBuf::from(output_dir);
+    let thumbnails_dir = output_dir.join("thumbnails");
+    fs::create_dir_all(&thumbnails_dir)?;
+    // Links are computed from the absolute location so `..` segments resolve as expected.
+    let link_base = std::path::absolute(&output_dir)?;
+
+    let mut cards = String::new();
+    for (index, video) in videos.iter().enumerate() {
+        // Thumbnails are copied under a per-card prefix since their source names aren't unique.
+        let thumbnail = video.thumbnail_path.as_deref()
+            .map(Path::new)
+            .filter(|path| path.is_file())
+            .and_then(|path| {
+                let name = format!("{}_{}", index, path.file_name()?.to_string_lossy());
+                fs::copy(path, thumbnails_dir.join(&name)).ok()?;
+                Some(format!("thumbnails/{}", name))
+            });
+
+        let image = match thumbnail {
+            Some(src) => format!(
+                "<img src=\"{}\" alt=\"{}\" loading=\"lazy\">",
+                escape_html(&encode_url_path(&src)),
+                escape_html(&video.file_name),
+            ),
+            None => "<div class=\"placeholder\"></div>".to_string(),
+        };
+
+        let _ = writeln!(
+            cards,
+            "<a class=\"card\" href=\"{}\">{}<div class=\"name\">{}</div><div class=\"details\">{}</div></a>",
+            escape_html(&video_href(&link_base, Path::new(&video.full_path))),
+            image,
+            escape_html(&video.file_name),
+            escape_html(&video_details(video)),
+        );
+    }
+
+    let html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n<style>{style}</style>\n</head>\n<body>\n\
+         <h1>{title} ({count} videos)</h1>\n<div class=\"grid\">\n{cards}</div>\n</body>\n</html>\n",
+        title = escape_html(&folder_name),
+        style = GALLERY_STYLE,
+        count = videos.len(),
+        cards = cards,
+    );
+
+    let index_path = output_dir.join("index.html");
+    fs::write(&index_path, html)?;
+    Ok(index_path.to_string_lossy().to_string())
+}
@@ -18,11 +18,44 @@ fn greet(name: &str) -> String {
 //     }
 // }
 
+mod animated_preview;
+mod audio;
+mod content_hash;
+mod database;
+mod error;
+mod ffmpeg_paths;
+mod gallery;
+mod library_file;
+mod logging;
+mod motion;
+mod operations;
+mod playback;
+mod power;
+mod pulled_crawls;
+mod roots;
+mod series;
+mod split_recordings;
+mod storyboard;
+mod subtitles;
+mod sync;
+mod tags;
+mod thumbnail_cache;
+mod transcode_queue;
+mod video_stream;
+mod watcher;
+
+use database::Database;
+use error::AppError;
 use serde::{Deserialize, Serialize};
+use subtitles::SubtitleTrack;
 use walkdir::WalkDir;
 use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::Notify;
 use std::time::SystemTime;
-use std::process::Command;
+use tokio::process::Command;
+use tauri::{AppHandle, Emitter, Manager, State};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct VideoMetadata {
@@ -39,6 +72,635 @@ pub struct VideoMetadata {
     pub fps: Option<f32>,
     pub codec: Option<String>,
     pub thumbnail_path: Option<String>,
+    /// Overall container bitrate in bits per second.
+    pub total_bitrate: Option<u64>,
+    /// Bitrate of the video stream in bits per second, when the container reports it.
+    pub video_bitrate: Option<u64>,
+    /// Whether the first video packet is a keyframe. `None` until checked, see
+    /// `find_videos_without_leading_keyframe`.
+    pub starts_with_keyframe: Option<bool>,
+    /// Clockwise rotation in degrees (0, 90, 180 or 270) that players apply on display.
+    pub rotation: Option<i32>,
+    /// Size of the picture as shown, i.e. corrected for the sample aspect ratio and rotation.
+    pub display_width: Option<u32>,
+    pub display_height: Option<u32>,
+    /// Width over height as displayed, below 1 for portrait videos.
+    pub aspect_ratio: Option<f64>,
+    pub resolution_class: Option<ResolutionClass>,
+    /// Color description of the video stream as reported by ffprobe, e.g. "bt2020nc",
+    /// "smpte2084" and "bt2020" for HDR10.
+    pub color_space: Option<String>,
+    pub color_transfer: Option<String>,
+    pub color_primaries: Option<String>,
+    /// Whether `color_transfer` is an HDR transfer function (PQ or HLG), which shows washed out
+    /// unless tone mapped.
+    #[serde(default)]
+    pub is_hdr: bool,
+    /// Codec profile as reported by ffprobe, e.g. "High" or "Main 10".
+    pub profile: Option<String>,
+    /// Codec level as reported by ffprobe (H.264: 51 = 5.1, HEVC: 153 = 5.1).
+    pub level: Option<i32>,
+    /// Heuristic 0-100 estimate of how hard the stream is to decode, see `decode_difficulty`.
+    /// Scores of `DECODE_DIFFICULTY_WARNING` and up are likely to stutter in the webview.
+    pub decode_difficulty: Option<u8>,
+    /// When the video was recorded according to the container's `creation_time` tag, in the same
+    /// format as `creation_date`. Unlike the filesystem date this survives copying.
+    pub media_creation_date: Option<String>,
+    /// `media_creation_date` where known and `creation_date` otherwise; sort by this for a
+    /// chronological view.
+    pub recorded_date: Option<String>,
+    /// Software that wrote the file, e.g. "Lavf60.3.100" (ffmpeg, OBS) or "HandBrake 1.6.1".
+    pub encoder: Option<String>,
+    /// Recording device from QuickTime/Android metadata, e.g. "Apple iPhone 13 Pro".
+    pub device_model: Option<String>,
+    /// Composite image highlighting where motion occurs, see `generate_motion_thumbnail`.
+    pub motion_thumbnail_path: Option<String>,
+    /// Container as reported by ffprobe's `format_name`, e.g. "mov,mp4,m4a,3gp,3g2,mj2".
+    pub container: Option<String>,
+    /// Whether `container` disagrees with the file extension, e.g. Matroska data in a `.mp4`.
+    /// `None` when either is unknown.
+    pub container_mismatch: Option<bool>,
+    /// Codec of the first audio stream, `None` for files without audio.
+    pub audio_codec: Option<String>,
+    /// Channel count and sample rate (Hz) of the first audio stream.
+    pub audio_channels: Option<u32>,
+    pub audio_sample_rate: Option<u32>,
+    /// Number of audio streams. `None` for rows indexed before it was stored.
+    pub audio_track_count: Option<u32>,
+    pub has_audio: bool,
+    /// Embedded subtitle and caption tracks, extracted with `subtitles::extract_subtitles`.
+    #[serde(default)]
+    pub subtitle_tracks: Vec<SubtitleTrack>,
+    /// Whether the average and nominal frame rates disagree, i.e. `fps` is only an average.
+    pub variable_frame_rate: bool,
+    /// Whether the webview can play the file as-is with the configured playback capabilities,
+    /// see `playback::web_ready`. `None` when the container or codec is unknown.
+    pub web_ready: Option<bool>,
+    /// Camera motion class ("static", "panning" or "shaky") if stored by
+    /// `motion::analyze_camera_motion`.
+    pub motion_type: Option<String>,
+    /// Which tool the metadata was read with: "ffprobe", or "mediainfo" for files ffprobe
+    /// couldn't read.
+    pub metadata_source: Option<String>,
+    /// `sha256:<hex>` of the file contents if computed by `content_hash::hash_video_content`
+    /// or a crawl with `CrawlOptions::full_content_hash`.
+    pub content_hash: Option<String>,
+    /// Whether the first `DECODE_CHECK_SECONDS` decode without errors, checked by crawls with
+    /// `CrawlOptions::verify`. `None` when not checked.
+    pub playable: Option<bool>,
+    /// `quick:<hex>` over the file size and its first and last MiB, see `content_hash::quick_hash`.
+    pub quick_hash: Option<String>,
+    /// 1-5 stars set with `set_rating`, `None` when unrated.
+    pub rating: Option<u8>,
+    /// Set with `toggle_favorite`.
+    #[serde(default)]
+    pub is_favorite: bool,
+    /// When the row was first inserted, as epoch millis. `None` for rows from before this was
+    /// tracked.
+    pub created_at: Option<String>,
+    /// When the row was last inserted or updated through `database::upsert_video`, as epoch
+    /// millis. `None` for rows from before this was tracked.
+    pub updated_at: Option<String>,
+}
+
+/// Decode difficulty score from which playback is likely to stutter and a transcode is advisable.
+pub const DECODE_DIFFICULTY_WARNING: u8 = 70;
+
+/// Estimates decode cost on a 0-100 scale from the codec, the pixel rate relative to 1080p30, the
+/// bitrate and whether the profile/level implies 10-bit, 4:2:2/4:4:4 or high-level streams that
+/// typically fall back to software decoding. 1080p30 H.264 scores about 20, 4K60 10-bit HEVC
+/// close to 100. Returns `None` without a codec or dimensions.
+fn decode_difficulty(video: &VideoMetadata) -> Option<u8> {
+    let codec = video.codec.as_deref()?.to_lowercase();
+    let width = video.display_width.or(video.width)? as f64;
+    let height = video.display_height.or(video.height)? as f64;
+    let fps = video.fps.filter(|fps| fps.is_finite() && *fps > 0.0).unwrap_or(30.0) as f64;
+    
+    let codec_weight = match codec.as_str() {
+        "h264" | "vp8" | "mpeg2video" => 1.0,
+        "mpeg4" | "mjpeg" => 0.8,
+        "vp9" => 1.4,
+        "hevc" => 1.6,
+        "av1" => 1.8,
+        "prores" | "dnxhd" => 2.0,
+        _ => 1.2,
+    };
+    let pixel_rate = width * height * fps / (1920.0 * 1080.0 * 30.0);
+    
+    let profile = video.profile.as_deref().unwrap_or("").to_lowercase();
+    let profile_weight = if profile.contains("4:4:4") || profile.contains("4:2:2") || profile.contains("rext") {
+        1.8
+    } else if profile.contains("10") || profile.contains("12") {
+        1.5
+    } else {
+        1.0
+    };
+    
+    // ffprobe reports H.264 levels times ten and HEVC levels times thirty.
+    let level = video.level.map(|level| match codec.as_str() {
+        "hevc" => level as f64 / 30.0,
+        _ => level as f64 / 10.0,
+    });
+    let level_weight = if level.is_some_and(|level| level >= 5.1) { 1.2 } else { 1.0 };
+    
+    let bitrate_mbps = video.video_bitrate.or(video.total_bitrate).unwrap_or(0) as f64 / 1_000_000.0;
+    
+    let raw = codec_weight * pixel_rate * profile_weight * level_weight + bitrate_mbps / 40.0;
+    Some((100.0 * (1.0 - (-raw / 4.0).exp())).round().clamp(0.0, 100.0) as u8)
+}
+
+/// Transfer functions of HDR video: PQ (HDR10, Dolby Vision) and HLG.
+const HDR_TRANSFERS: &[&str] = &["smpte2084", "arib-std-b67"];
+
+fn is_hdr_transfer(transfer: &str) -> bool {
+    HDR_TRANSFERS.contains(&transfer)
+}
+
+/// Coarse size bucket of a video as displayed, stored by name.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ResolutionClass {
+    #[serde(rename = "SD")]
+    Sd,
+    #[serde(rename = "HD")]
+    Hd,
+    #[serde(rename = "FullHD")]
+    FullHd,
+    #[serde(rename = "QHD")]
+    Qhd,
+    #[serde(rename = "UHD4K")]
+    Uhd4k,
+    /// Larger than 4K, e.g. 5K or 8K.
+    Other,
+}
+
+impl ResolutionClass {
+    const ALL: [ResolutionClass; 6] = [Self::Sd, Self::Hd, Self::FullHd, Self::Qhd, Self::Uhd4k, Self::Other];
+
+    /// Classifies by the height a 16:9 frame of the same size would have, so portrait and
+    /// letterboxed videos land with their landscape counterparts (1080x1920 and 1920x800 are
+    /// both FullHD). The bounds sit between the standard heights to allow for cropping.
+    pub fn of(width: u32, height: u32) -> Option<Self> {
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let (long, short) = (width.max(height) as f64, width.min(height) as f64);
+        let equivalent_height = short.max(long * 9.0 / 16.0);
+        Some(match equivalent_height {
+            h if h < 600.0 => Self::Sd,
+            h if h < 900.0 => Self::Hd,
+            h if h < 1260.0 => Self::FullHd,
+            h if h < 1800.0 => Self::Qhd,
+            h if h < 2600.0 => Self::Uhd4k,
+            _ => Self::Other,
+        })
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Sd => "SD",
+            Self::Hd => "HD",
+            Self::FullHd => "FullHD",
+            Self::Qhd => "QHD",
+            Self::Uhd4k => "UHD4K",
+            Self::Other => "Other",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|class| class.as_str() == name)
+    }
+}
+
+/// File extensions (lowercase) treated as videos when crawling.
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "avi", "mov", "mkv", "webm", "flv", "wmv", "m4v"];
+
+fn is_video_file(path: &std::path::Path) -> bool {
+    has_extension(path, VIDEO_EXTENSIONS)
+}
+
+/// Whether the extension of `path` is one of `extensions`, ignoring case and a leading dot.
+fn has_extension<S: AsRef<str>>(path: &std::path::Path, extensions: &[S]) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extensions.iter().any(|entry| {
+            entry.as_ref().trim().trim_start_matches('.').eq_ignore_ascii_case(extension)
+        }))
+}
+
+/// The extensions crawled unless `CrawlOptions::extensions` says otherwise.
+#[tauri::command]
+async fn get_default_video_extensions() -> Result<Vec<String>, AppError> {
+    Ok(VIDEO_EXTENSIONS.iter().map(|extension| extension.to_string()).collect())
+}
+
+/// Result of a dry-run walk: what a crawl of the directory would process.
+#[derive(Debug, Serialize, Clone)]
+pub struct ScanSummary {
+    pub video_count: u64,
+    /// Combined size of the video files in bytes.
+    pub total_size: u64,
+}
+
+/// Crawl settings. Every field defaults to the behavior of a plain `crawl_directory` call.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CrawlOptions {
+    /// Don't descend into directories on a different filesystem than the root, like `find -xdev`.
+    pub same_file_system: bool,
+    /// File extensions to crawl instead of `VIDEO_EXTENSIONS`, e.g. `["mp4", "ts", "mts"]`.
+    pub extensions: Option<Vec<String>>,
+    /// How many levels below the root to visit: 1 means only files directly in it. Unlimited
+    /// when unset.
+    pub max_depth: Option<usize>,
+    /// Seconds to wait for ffprobe on one file before skipping it, `DEFAULT_PROBE_TIMEOUT` when
+    /// unset. Raise it for slow network mounts.
+    pub probe_timeout_secs: Option<u64>,
+    /// Also hash the whole of every file into `content_hash`. Slow, it reads every byte.
+    pub full_content_hash: bool,
+    /// Glob patterns of entries to skip; a matching directory isn't descended into. Patterns
+    /// containing a `/` are matched against the full path with `/` separators (e.g.
+    /// `**/@eaDir`), others against the file or directory name (e.g. `Sample*`). `*` stays
+    /// within one path component, `**` spans any number. Case-insensitive on Windows.
+    pub exclude: Option<Vec<String>>,
+    /// Skip files smaller or larger than this many bytes. Checked before probing, so
+    /// zero-byte placeholders never reach ffprobe with a `min_size` of 1.
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    /// Decode the first seconds of every file to fill in `playable`. Slower, so off by default.
+    pub verify: bool,
+    /// How dates are written in the returned videos. The library always stores epoch millis.
+    pub timestamp_format: TimestampFormat,
+    /// Descend into symlinked directories and pick up symlinked files. A directory reached
+    /// through several links is crawled once, and links looping back up the tree are skipped.
+    pub follow_symlinks: bool,
+}
+
+/// How long ffprobe may take on one file before it is killed (15 s).
+const DEFAULT_PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// How long the frame analyses behind the thumbnail strategies, and the frame grab itself, may
+/// take: they decode up to a minute of video.
+const FRAME_ANALYSIS_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Time allowed for a job that decodes all of a video of `duration` seconds: a minute plus
+/// twice the duration, or an hour when the duration isn't known.
+fn full_decode_timeout(duration: Option<f64>) -> std::time::Duration {
+    match duration.filter(|d| d.is_finite() && *d > 0.0) {
+        Some(duration) => std::time::Duration::from_secs(60) + std::time::Duration::from_secs_f64(duration * 2.0),
+        None => std::time::Duration::from_secs(60 * 60),
+    }
+}
+
+/// Runs `command` to completion without blocking the async runtime, and kills it once
+/// `timeout` has passed (e.g. on a stalled network mount). `program` names it in errors.
+async fn output_with_timeout(command: &mut Command, program: &str, timeout: std::time::Duration) -> Result<std::process::Output, AppError> {
+    let output = command.kill_on_drop(true).output();
+    match tokio::time::timeout(timeout, output).await {
+        Ok(output) => output.map_err(|e| AppError::spawn(program, e)),
+        Err(_) => Err(AppError::Timeout(format!("{} timed out", program))),
+    }
+}
+
+impl CrawlOptions {
+    fn probe_timeout(&self) -> std::time::Duration {
+        self.probe_timeout_secs.map(std::time::Duration::from_secs).unwrap_or(DEFAULT_PROBE_TIMEOUT)
+    }
+    
+    /// Whether `path` has one of the extensions this crawl picks up.
+    fn is_video_file(&self, path: &std::path::Path) -> bool {
+        match &self.extensions {
+            Some(extensions) => has_extension(path, extensions),
+            None => is_video_file(path),
+        }
+    }
+    
+    /// Whether the walked `entry` is a file this crawl processes: a video by extension within
+    /// the size limits.
+    fn selects(&self, entry: &walkdir::DirEntry) -> bool {
+        if !entry.file_type().is_file() || !self.is_video_file(entry.path()) {
+            return false;
+        }
+        if self.min_size.is_none() && self.max_size.is_none() {
+            return true;
+        }
+        let Ok(metadata) = entry.metadata() else { return false };
+        self.min_size.is_none_or(|min| metadata.len() >= min) && self.max_size.is_none_or(|max| metadata.len() <= max)
+    }
+}
+
+/// `CrawlOptions::exclude` compiled, each with whether it is matched against the full path.
+fn exclude_patterns(options: &CrawlOptions) -> Result<Vec<(glob::Pattern, bool)>, AppError> {
+    options.exclude.iter().flatten()
+        .map(|pattern| {
+            let pattern = pattern.replace('\\', "/");
+            let compiled = glob::Pattern::new(&pattern)
+                .map_err(|e| AppError::InvalidInput(format!("Invalid exclude pattern '{}': {}", pattern, e)))?;
+            Ok((compiled, pattern.contains('/')))
+        })
+        .collect()
+}
+
+fn is_excluded(patterns: &[(glob::Pattern, bool)], path: &std::path::Path) -> bool {
+    let match_options = glob::MatchOptions {
+        case_sensitive: !cfg!(windows),
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+    let full_path = path.to_string_lossy().replace('\\', "/");
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    patterns.iter().any(|(pattern, on_full_path)| {
+        pattern.matches_with(if *on_full_path { &full_path } else { &name }, match_options)
+    })
+}
+
+/// The directory walk shared by crawls and scans, configured from `options`. Fails on invalid
+/// exclude patterns.
+fn crawl_walker(path: &str, options: &CrawlOptions) -> Result<impl Iterator<Item = walkdir::Result<walkdir::DirEntry>>, AppError> {
+    let patterns = exclude_patterns(options)?;
+    let walker = WalkDir::new(path)
+        .same_file_system(options.same_file_system)
+        .follow_links(options.follow_symlinks);
+    let walker = match options.max_depth {
+        Some(max_depth) => walker.max_depth(max_depth),
+        None => walker,
+    };
+    let follow_symlinks = options.follow_symlinks;
+    let mut visited_dirs = std::collections::HashSet::new();
+    // The root itself is never excluded.
+    Ok(walker.into_iter().filter_entry(move |entry| {
+        if entry.depth() > 0 && is_excluded(&patterns, entry.path()) {
+            return false;
+        }
+        // walkdir catches links back to an ancestor, not two links to the same directory.
+        if follow_symlinks && entry.file_type().is_dir() {
+            if let Ok(canonical) = fs::canonicalize(entry.path()) {
+                return visited_dirs.insert(canonical);
+            }
+        }
+        true
+    }))
+}
+
+/// Identifies the filesystem (device or volume) a file lives on.
+#[cfg(unix)]
+fn file_system_id(path: &std::path::Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|metadata| metadata.dev())
+}
+
+#[cfg(windows)]
+fn file_system_id(path: &std::path::Path) -> Option<u64> {
+    let handle = winapi_util::Handle::from_path_any(path).ok()?;
+    winapi_util::file::information(&handle).ok().map(|info| info.volume_serial_number())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn file_system_id(_path: &std::path::Path) -> Option<u64> {
+    None
+}
+
+/// Stops early, with what was counted so far, once `cancel` is set.
+fn scan_summary(path: &str, options: &CrawlOptions, cancel: Option<&AtomicBool>) -> Result<ScanSummary, AppError> {
+    let mut summary = ScanSummary { video_count: 0, total_size: 0 };
+    for entry in crawl_walker(path, options)?.filter_map(|e| e.ok()) {
+        if cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+            break;
+        }
+        if options.selects(&entry) {
+            summary.video_count += 1;
+            summary.total_size += entry.metadata().map(|m| m.len()).unwrap_or(0);
+        }
+    }
+    Ok(summary)
+}
+
+/// Counts the videos under `path` without probing them. This only walks the directory tree,
+/// so it is fast enough to run before a crawl to size its progress bar.
+#[tauri::command]
+async fn scan_directory_summary(path: String, options: Option<CrawlOptions>) -> Result<ScanSummary, AppError> {
+    scan_summary(&path, &options.unwrap_or_default(), None)
+}
+
+/// Shorthand for `scan_directory_summary` with only `extensions` and `exclude` set, see
+/// `CrawlOptions`.
+#[tauri::command]
+async fn count_videos(path: String, extensions: Option<Vec<String>>, exclude: Option<Vec<String>>) -> Result<ScanSummary, AppError> {
+    scan_summary(&path, &CrawlOptions { extensions, exclude, ..Default::default() }, None)
+}
+
+/// How long `validate_crawl_target` waits for a directory listing before reporting it unreachable.
+const CRAWL_TARGET_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CrawlTargetValidation {
+    /// The directory exists and could be listed.
+    pub ok: bool,
+    pub is_network: bool,
+    /// Entries directly inside the directory; nested ones are not counted.
+    pub estimated_entries: u64,
+    /// Why the directory can't be crawled, or what may surprise, e.g. that it is empty.
+    pub warning: Option<String>,
+}
+
+/// Whether `path` is on a network share: UNC paths and mapped network drives.
+#[cfg(windows)]
+fn is_network_path(path: &str) -> bool {
+    use windows_sys::Win32::Storage::FileSystem::GetDriveTypeW;
+    use windows_sys::Win32::System::WindowsProgramming::DRIVE_REMOTE;
+
+    let path = database::normalize_path(path);
+    if path.starts_with("\\\\") {
+        return true;
+    }
+    let Some(drive) = path.get(..2).filter(|drive| drive.ends_with(':')) else { return false };
+    let root: Vec<u16> = format!("{}\\", drive).encode_utf16().chain(std::iter::once(0)).collect();
+    // SAFETY: `root` is a NUL-terminated UTF-16 string that outlives the call.
+    unsafe { GetDriveTypeW(root.as_ptr()) == DRIVE_REMOTE }
+}
+
+/// Whether `path` is on a network filesystem, going by the mount it is under.
+#[cfg(target_os = "linux")]
+fn is_network_path(path: &str) -> bool {
+    const NETWORK_FILE_SYSTEMS: &[&str] = &["nfs", "nfs4", "cifs", "smb3", "smbfs", "fuse.sshfs", "9p", "davfs", "afpfs"];
+
+    let path = std::path::Path::new(path);
+    let mounts = fs::read_to_string("/proc/self/mounts").unwrap_or_default();
+    // The mount point with the longest matching prefix is the one the path lives on.
+    mounts.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?.replace("\\040", " ");
+            let file_system = fields.next()?;
+            path.starts_with(&mount_point).then_some((mount_point.len(), file_system))
+        })
+        .max_by_key(|(len, _)| *len)
+        .is_some_and(|(_, file_system)| NETWORK_FILE_SYSTEMS.contains(&file_system))
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn is_network_path(path: &str) -> bool {
+    path.starts_with("//") || path.starts_with("\\\\")
+}
+
+/// Checks that `path` is a directory that can be listed, giving up after a few seconds so an
+/// offline share doesn't hang the directory picker. Nothing below the top level is read.
+#[tauri::command]
+async fn validate_crawl_target(path: String) -> Result<CrawlTargetValidation, AppError> {
+    let is_network = is_network_path(&path);
+    let failed = |warning: String| CrawlTargetValidation { ok: false, is_network, estimated_entries: 0, warning: Some(warning) };
+    
+    let directory = path.clone();
+    let listing = tauri::async_runtime::spawn_blocking(move || -> std::io::Result<u64> {
+        let metadata = fs::metadata(&directory)?;
+        if !metadata.is_dir() {
+            return Err(std::io::Error::other("Not a directory"));
+        }
+        Ok(fs::read_dir(&directory)?.count() as u64)
+    });
+    
+    let entries = match tokio::time::timeout(CRAWL_TARGET_TIMEOUT, listing).await {
+        Err(_) => return Ok(failed(format!("{} did not respond within {} seconds", path, CRAWL_TARGET_TIMEOUT.as_secs()))),
+        Ok(joined) => joined?,
+    };
+    match entries {
+        Ok(0) => Ok(CrawlTargetValidation { ok: true, is_network, estimated_entries: 0, warning: Some("Directory is empty".to_string()) }),
+        Ok(estimated_entries) => Ok(CrawlTargetValidation { ok: true, is_network, estimated_entries, warning: None }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(failed(format!("{} does not exist", path))),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => Ok(failed(format!("Permission denied for {}", path))),
+        Err(e) => Ok(failed(e.to_string())),
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CrawlPhase {
+    /// Counting files; `total` is not known yet.
+    Scanning,
+    /// Probing files one by one.
+    Extracting,
+}
+
+/// Payload of the "crawl-progress" event.
+#[derive(Debug, Serialize, Clone)]
+pub struct CrawlProgress {
+    pub phase: CrawlPhase,
+    pub processed: u64,
+    pub total: Option<u64>,
+    pub current_path: Option<String>,
+    /// Estimated seconds until extraction completes, from the average time per file so far.
+    pub eta_seconds: Option<f64>,
+}
+
+/// Payload of the "crawl-complete" event sent when a crawl finishes.
+#[derive(Debug, Serialize, Clone)]
+pub struct CrawlComplete {
+    /// The crawled root, the first one of a `crawl_directories` call.
+    pub path: String,
+    /// Video files processed, including those whose metadata couldn't be read.
+    pub processed: u64,
+    /// Videos whose metadata was extracted.
+    pub video_count: u64,
+    /// Other files that were passed over, see `CrawlReport::skipped_files`.
+    pub skipped_files: u64,
+}
+
+/// Minimum time between two "crawl-progress" events while extracting, so that crawls over tens
+/// of thousands of files don't flood the event bus.
+const CRAWL_PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Tracks when the next "crawl-progress" event is due.
+struct ProgressPacer {
+    last_emit: Option<std::time::Instant>,
+}
+
+impl ProgressPacer {
+    fn new() -> Self {
+        ProgressPacer { last_emit: None }
+    }
+    
+    /// Whether an event is due now; the first call always is. Counts as emitting if so.
+    fn due(&mut self) -> bool {
+        let due = self.last_emit.is_none_or(|last| last.elapsed() >= CRAWL_PROGRESS_INTERVAL);
+        if due {
+            self.last_emit = Some(std::time::Instant::now());
+        }
+        due
+    }
+}
+
+/// Videos found on one filesystem during a crawl.
+#[derive(Debug, Serialize, Clone)]
+pub struct MountStats {
+    /// Device (Unix) or volume serial number (Windows); `None` if it couldn't be read.
+    pub file_system_id: Option<u64>,
+    /// The first directory a video on this filesystem was found in, usually at or just below
+    /// the mount point.
+    pub first_directory: String,
+    pub video_count: u64,
+    pub total_size: u64,
+    /// Average time spent probing one of its videos, in milliseconds.
+    pub average_probe_ms: f64,
+}
+
+/// A file or directory a crawl had to skip.
+#[derive(Debug, Serialize, Clone)]
+pub struct CrawlError {
+    pub path: String,
+    pub message: String,
+}
+
+/// Everything a crawl found, see `crawl_directory_report`.
+#[derive(Debug, Serialize, Clone)]
+pub struct CrawlReport {
+    pub videos: Vec<VideoMetadata>,
+    /// One entry per filesystem, in the order they were first reached.
+    pub mounts: Vec<MountStats>,
+    /// Files whose metadata couldn't be read and directory entries that couldn't be listed.
+    pub errors: Vec<CrawlError>,
+    /// Files passed over without probing: other file types and videos outside the size limits.
+    /// Excluded directories aren't entered, so their files aren't counted.
+    pub skipped_files: u64,
+    /// The crawl was stopped by `cancel_crawl`; the fields above cover the files reached so far.
+    pub cancelled: bool,
+}
+
+/// Cancellation flags of the running crawls that were started with a `crawl_id`.
+#[derive(Default)]
+pub struct CrawlCancellations(Mutex<std::collections::HashMap<String, Arc<AtomicBool>>>);
+
+impl CrawlCancellations {
+    fn flags(&self) -> MutexGuard<'_, std::collections::HashMap<String, Arc<AtomicBool>>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Runs `crawl`, cancellable through `cancel_crawl(crawl_id)` if an id is given.
+async fn cancellable_crawl(app: &AppHandle, roots: &[&str], options: &CrawlOptions, crawl_id: Option<String>) -> Result<CrawlReport, AppError> {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let cancellations = app.state::<CrawlCancellations>();
+    if let Some(crawl_id) = &crawl_id {
+        cancellations.flags().insert(crawl_id.clone(), cancelled.clone());
+    }
+    
+    let report = crawl(app, roots, options, None, Some(&cancelled)).await;
+    
+    // A second crawl may have been started under the same id in the meantime; leave its flag.
+    if let Some(crawl_id) = &crawl_id {
+        let mut flags = cancellations.flags();
+        if flags.get(crawl_id).is_some_and(|flag| Arc::ptr_eq(flag, &cancelled)) {
+            flags.remove(crawl_id);
+        }
+    }
+    report
+}
+
+/// Stops the crawl started with `crawl_id`. It returns what it found so far.
+#[tauri::command]
+async fn cancel_crawl(cancellations: State<'_, CrawlCancellations>, crawl_id: String) -> Result<(), AppError> {
+    let flags = cancellations.flags();
+    let flag = flags.get(&crawl_id).ok_or_else(|| AppError::NotFound(format!("Crawl not found: {}", crawl_id)))?;
+    flag.store(true, Ordering::Relaxed);
+    Ok(())
 }
 
 #[tauri::command]
@@ -46,53 +708,228 @@ pub struct VideoMetadata {
 /// 
 /// # Arguments
 /// * `path` - The root directory path to start crawling from.
+/// * `options` - Optional `CrawlOptions`, e.g. to stay on the root's filesystem or to crawl
+///   other file extensions.
+/// * `crawl_id` - Optional id under which the crawl can be stopped with `cancel_crawl`. A
+///   cancelled crawl returns the videos found up to that point.
+/// 
+/// While on battery the crawl is slowed down or paused according to the stored `BatteryPolicy`.
+/// 
+/// Progress is reported through "crawl-progress" events (`CrawlProgress`): one for the initial
+/// scanning phase, then at most every `CRAWL_PROGRESS_INTERVAL` while extracting. A
+/// "crawl-complete" event (`CrawlComplete`) is sent at the end.
 /// 
 /// # Returns
-/// * `Result<Vec<VideoMetadata>, String>` - On success, returns a vector of `VideoMetadata` for each video file found. On failure, returns an error message.
-async fn crawl_directory(path: String) -> Result<Vec<VideoMetadata>, String> {
+/// * `Result<Vec<VideoMetadata>, AppError>` - On success, returns a vector of `VideoMetadata` for each video file found. On failure, returns the error.
+///   Files whose metadata can't be read are left out; `crawl_directory_report` lists them.
+async fn crawl_directory(app: AppHandle, path: String, options: Option<CrawlOptions>, crawl_id: Option<String>) -> Result<Vec<VideoMetadata>, AppError> {
+    Ok(cancellable_crawl(&app, &[&path], &options.unwrap_or_default(), crawl_id).await?.videos)
+}
+
+/// Like `crawl_directory` over several roots at once, e.g. one per drive, with progress events
+/// against the combined total. Roots inside another root are skipped and a video reachable
+/// from more than one root is returned once.
+#[tauri::command]
+async fn crawl_directories(app: AppHandle, paths: Vec<String>, options: Option<CrawlOptions>, crawl_id: Option<String>) -> Result<Vec<VideoMetadata>, AppError> {
+    let roots: Vec<&str> = paths.iter().map(String::as_str).collect();
+    Ok(cancellable_crawl(&app, &roots, &options.unwrap_or_default(), crawl_id).await?.videos)
+}
+
+/// Like `crawl_directory`, but also reports the files that couldn't be read and per-filesystem
+/// statistics, so a root spanning several mounts can be broken down.
+#[tauri::command]
+async fn crawl_directory_report(app: AppHandle, path: String, options: Option<CrawlOptions>, crawl_id: Option<String>) -> Result<CrawlReport, AppError> {
+    cancellable_crawl(&app, &[&path], &options.unwrap_or_default(), crawl_id).await
+}
+
+/// Roots with those inside another root (or repeated) left out, so no directory is walked twice.
+fn distinct_roots<'a>(roots: &[&'a str]) -> Vec<&'a str> {
+    let canonical: Vec<std::path::PathBuf> = roots.iter()
+        .map(|root| fs::canonicalize(root).unwrap_or_else(|_| std::path::PathBuf::from(root)))
+        .collect();
+    roots.iter()
+        .enumerate()
+        // A root goes when another one contains it; of equal roots the first stays.
+        .filter(|(index, _)| !canonical.iter().enumerate().any(|(other, path)| {
+            other != *index && canonical[*index].starts_with(path) && (canonical[*index] != *path || other < *index)
+        }))
+        .map(|(_, root)| *root)
+        .collect()
+}
+
+/// Crawls `roots` as one crawl with a combined total; files reached from more than one root
+/// (e.g. through symlinks) are only processed once. With a `sink`, each extracted video is sent
+/// there as soon as it is ready instead of being collected into the report, waiting while the
+/// channel is full; the crawl stops early once the receiving end is dropped. It also stops, with
+/// `cancelled` set in the report, once `cancel` is set.
+async fn crawl(
+    app: &AppHandle,
+    roots: &[&str],
+    options: &CrawlOptions,
+    sink: Option<&tokio::sync::mpsc::Sender<VideoMetadata>>,
+    cancel: Option<&Arc<AtomicBool>>,
+) -> Result<CrawlReport, AppError> {
     // Create a vector to store metadata for each discovered video file.
     let mut videos = Vec::new();
+    // Per-filesystem totals: (stats, total probe time).
+    let mut mounts: Vec<(MountStats, std::time::Duration)> = Vec::new();
+    let is_cancelled = || cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed));
+    
+    // Consulted after every probed file to ease off while running on battery.
+    let mut throttle = power::BatteryThrottle::new(power::battery_policy(app));
+    // Used to fill in each video's `web_ready` verdict.
+    let capabilities = playback::playback_capabilities(&app.state::<Database>().conn());
 
-    // Define a list of file extensions that are considered video files.
-    let video_extensions = ["mp4", "avi", "mov", "mkv", "webm", "flv", "wmv", "m4v"];
-    
-    // Walk through the directory tree starting from the given path.
-    // `WalkDir::new(&path)` creates an iterator over all entries (files and directories).
-    // `.into_iter()` turns it into an iterator.
-    // `.filter_map(|e| e.ok())` skips over entries that resulted in an error, only keeping successful ones.
-    for entry in WalkDir::new(&path).into_iter().filter_map(|e| e.ok()) {
-        // Check if the current entry is a file (not a directory).
-        if entry.file_type().is_file() {
-            // Try to get the file extension of the current file.
-            if let Some(extension) = entry.path().extension() {
-                // Convert the extension to a string slice for comparison.
-                if let Some(ext_str) = extension.to_str() {
-                    // Convert the extension to lowercase and check if it matches any known video extension.
-                    if video_extensions.contains(&ext_str.to_lowercase().as_str()) {
-                        // If the file is a video, attempt to extract its metadata asynchronously.
-                        match extract_video_metadata(entry.path()).await {
-                            // On success, add the metadata to the videos vector.
-                            Ok(metadata) => videos.push(metadata),
-                            // On failure, print an error message to standard error, but continue processing other files.
-                            Err(e) => eprintln!("Error processing {}: {}", entry.path().display(), e),
+    // Count the videos first so extraction progress has a total and an ETA. The walk runs on a
+    // blocking thread, since large or network trees take a while, and stops when cancelled.
+    let _ = app.emit("crawl-progress", CrawlProgress {
+        phase: CrawlPhase::Scanning,
+        processed: 0,
+        total: None,
+        current_path: None,
+        eta_seconds: None,
+    });
+    let roots = distinct_roots(roots);
+    let total = {
+        let (roots, options, cancel) = (
+            roots.iter().map(|root| root.to_string()).collect::<Vec<_>>(),
+            options.clone(),
+            cancel.cloned(),
+        );
+        tauri::async_runtime::spawn_blocking(move || {
+            roots.iter().try_fold(0, |total, root| {
+                Ok::<_, AppError>(total + scan_summary(root, &options, cancel.as_deref())?.video_count)
+            })
+        })
+        .await
+        .map_err(|e| e.to_string())??
+    };
+    let walkers = roots.iter()
+        .map(|root| Ok(crawl_walker(root, options)?.map(move |entry| (*root, entry))))
+        .collect::<Result<Vec<_>, AppError>>()?;
+    let mut seen = std::collections::HashSet::new();
+    let extraction_started = std::time::Instant::now();
+    let mut pacer = ProgressPacer::new();
+    let mut processed: u64 = 0;
+    let mut video_count: u64 = 0;
+    let mut skipped_files: u64 = 0;
+    let mut cancelled = false;
+    let mut errors = Vec::new();
+    
+    // Walk through the directory trees starting from the given roots, one after the other.
+    // `crawl_walker` creates an iterator over all entries (files and directories).
+    for (root, entry) in walkers.into_iter().flatten() {
+        if is_cancelled() {
+            cancelled = true;
+            break;
+        }
+        if sink.is_some_and(|sink| sink.is_closed()) {
+            break;
+        }
+        
+        // Entries that can't be read (e.g. a directory without permission) are reported and skipped.
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                errors.push(CrawlError {
+                    path: e.path().map(|p| p.to_string_lossy().to_string()).unwrap_or_else(|| root.to_string()),
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+        
+        // Check if the current entry is a video file (a file with one of the crawled extensions).
+        if options.selects(&entry) {
+            if roots.len() > 1 && !seen.insert(fs::canonicalize(entry.path()).unwrap_or_else(|_| entry.path().to_path_buf())) {
+                continue;
+            }
+            // If the file is a video, attempt to extract its metadata asynchronously.
+            let started = std::time::Instant::now();
+            let extracted = extract_for_crawl(entry.path(), options).await;
+            let probe_time = started.elapsed();
+            
+            // Attribute the file to its filesystem for the per-mount breakdown.
+            let file_system_id = file_system_id(entry.path());
+            let mount_index = match mounts.iter().position(|(mount, _)| mount.file_system_id == file_system_id) {
+                Some(index) => index,
+                None => {
+                    let first_directory = entry.path().parent().unwrap_or(entry.path());
+                    mounts.push((MountStats {
+                        file_system_id,
+                        first_directory: first_directory.to_string_lossy().to_string(),
+                        video_count: 0,
+                        total_size: 0,
+                        average_probe_ms: 0.0,
+                    }, std::time::Duration::ZERO));
+                    mounts.len() - 1
+                }
+            };
+            let (mount, mount_probe_time) = &mut mounts[mount_index];
+            mount.video_count += 1;
+            mount.total_size += entry.metadata().map(|m| m.len()).unwrap_or(0);
+            *mount_probe_time += probe_time;
+            
+            match extracted {
+                // On success, add the metadata to the videos vector.
+                Ok(mut metadata) => {
+                    metadata.web_ready = playback::web_ready(&metadata, &capabilities);
+                    video_count += 1;
+                    match sink {
+                        Some(sink) => {
+                            if sink.send(metadata).await.is_err() {
+                                break;
+                            }
                         }
+                        None => videos.push(metadata),
                     }
                 }
+                // On failure, record the error for the report, but continue processing other files.
+                Err(e) => errors.push(CrawlError {
+                    path: entry.path().to_string_lossy().to_string(),
+                    message: e.to_string(),
+                }),
+            }
+            // Paused on battery, a crawl still stops once cancelled or no longer polled.
+            throttle.pace(probe_time, || is_cancelled() || sink.is_some_and(|sink| sink.is_closed())).await;
+
+            // Files added since the scan can push `processed` past `total`; the ETA then bottoms out at zero.
+            processed += 1;
+            if pacer.due() {
+                let per_file = extraction_started.elapsed().as_secs_f64() / processed as f64;
+                let _ = app.emit("crawl-progress", CrawlProgress {
+                    phase: CrawlPhase::Extracting,
+                    processed,
+                    total: Some(total),
+                    current_path: Some(entry.path().to_string_lossy().to_string()),
+                    eta_seconds: Some(per_file * total.saturating_sub(processed) as f64),
+                });
             }
+        } else if entry.file_type().is_file() {
+            skipped_files += 1;
         }
     }
     
+    let _ = app.emit("crawl-complete", CrawlComplete {
+        path: roots.first().unwrap_or(&"").to_string(),
+        processed,
+        video_count,
+        skipped_files,
+    });
+    
+    let mounts = mounts.into_iter()
+        .map(|(mut mount, probe_time)| {
+            mount.average_probe_ms = probe_time.as_secs_f64() * 1000.0 / mount.video_count as f64;
+            mount
+        })
+        .collect();
+    
     // Return the collected video metadata as a successful result.
-    Ok(videos)
+    Ok(CrawlReport { videos, mounts, errors, skipped_files, cancelled })
 }
 
-/// Asynchronously extracts metadata for a single video file at the given path.
-/// Returns a `VideoMetadata` struct on success, or an error message string on failure.
-async fn extract_video_metadata(path: &std::path::Path) -> Result<VideoMetadata, String> {
-    // Attempt to retrieve the file system metadata for the given path (e.g., size, timestamps).
-    // If this fails (e.g., file doesn't exist or permission denied), convert the error to a string and return it.
-    let metadata = fs::metadata(path).map_err(|e| e.to_string())?;
-
+/// Returns the `(folder_name, file_name)` pair stored for a video at `path`.
+fn path_names(path: &std::path::Path) -> (String, String) {
     // Extract the file name from the path.
     // `file_name()` returns an Option<&OsStr>, so we convert it to a string slice if possible.
     // If the file name can't be determined, use "unknown" as a fallback.
@@ -110,11 +947,66 @@ async fn extract_video_metadata(path: &std::path::Path) -> Result<VideoMetadata,
         .unwrap_or("unknown")
         .to_string();
 
+    (folder_name, file_name)
+}
+
+/// Creates the ID of a video by combining the folder and file name.
+fn make_video_id(folder_name: &str, file_name: &str) -> String {
+    format!("{}_{}", folder_name, file_name)
+}
+
+/// Seconds decoded by `decode_check`.
+const DECODE_CHECK_SECONDS: u32 = 5;
+
+/// Decodes the first `DECODE_CHECK_SECONDS` of `path` and reports whether that worked without
+/// errors. Partial downloads often probe fine but fail here. A decode that doesn't finish
+/// within `timeout` counts as unplayable.
+async fn decode_check(path: &std::path::Path, timeout: std::time::Duration) -> Result<bool, AppError> {
+    let mut decode = Command::new(ffmpeg_paths::ffmpeg());
+    decode
+        .args(["-v", "error", "-t", &DECODE_CHECK_SECONDS.to_string(), "-i"])
+        .arg(path)
+        .args(["-f", "null", "-"]);
+    match output_with_timeout(&mut decode, "ffmpeg", timeout).await {
+        Ok(output) => Ok(output.status.success() && String::from_utf8_lossy(&output.stderr).trim().is_empty()),
+        Err(AppError::Timeout(_)) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// `extract_video_metadata` with the extras `options` asks for.
+async fn extract_for_crawl(path: &std::path::Path, options: &CrawlOptions) -> Result<VideoMetadata, AppError> {
+    let mut video = extract_video_metadata(path, options.probe_timeout()).await?;
+    video.set_timestamp_format(options.timestamp_format);
+    if options.verify {
+        video.playable = Some(decode_check(path, options.probe_timeout()).await?);
+    }
+    if options.full_content_hash {
+        let path = path.to_path_buf();
+        video.content_hash = Some(
+            tauri::async_runtime::spawn_blocking(move || content_hash::full_hash(&path))
+                .await
+                .map_err(|e| e.to_string())??,
+        );
+    }
+    Ok(video)
+}
+
+/// Asynchronously extracts metadata for a single video file at the given path.
+/// Returns a `VideoMetadata` struct on success, or an `AppError` describing the failure.
+async fn extract_video_metadata(path: &std::path::Path, probe_timeout: std::time::Duration) -> Result<VideoMetadata, AppError> {
+    // Attempt to retrieve the file system metadata for the given path (e.g., size, timestamps).
+    // If this fails (e.g., file doesn't exist or permission denied), the error is returned as
+    // FileNotFound or PermissionDenied.
+    let metadata = fs::metadata(path)?;
+
+    let (folder_name, file_name) = path_names(path);
+
     // Attempt to get the file's creation time as a SystemTime.
     // If not available (e.g., on some platforms), use UNIX_EPOCH (Jan 1, 1970).
     // Then, calculate the number of seconds since UNIX_EPOCH.
     let creation_time = metadata.created()
-        .unwrap_or_else(|_| SystemTime::UNIX_EPOCH)
+        .unwrap_or(SystemTime::UNIX_EPOCH)
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap_or_default()
         .as_secs();
@@ -123,23 +1015,40 @@ async fn extract_video_metadata(path: &std::path::Path) -> Result<VideoMetadata,
     // If not available, use UNIX_EPOCH.
     // Then, calculate the number of seconds since UNIX_EPOCH.
     let modified_time = metadata.modified()
-        .unwrap_or_else(|_| SystemTime::UNIX_EPOCH)
+        .unwrap_or(SystemTime::UNIX_EPOCH)
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap_or_default()
         .as_secs();
 
     // Use ffmpeg to extract video-specific metadata (duration, resolution, codec, etc.).
-    // This is an async operation and may fail, in which case the error is propagated.
-    let video_info = extract_ffmpeg_metadata(path).await?;
+    // This is an async operation and may fail; mediainfo is then tried, if installed, before
+    // the error is propagated. A file that made ffprobe hang would likely hang mediainfo as
+    // well, so timeouts are returned right away.
+    let (video_info, metadata_source) = match extract_ffmpeg_metadata(path, probe_timeout).await {
+        Ok(video_info) => (video_info, "ffprobe"),
+        Err(ffprobe_error @ AppError::Timeout(_)) => return Err(ffprobe_error),
+        Err(ffprobe_error) => match extract_mediainfo_metadata(path, probe_timeout).await {
+            Ok(video_info) => (video_info, "mediainfo"),
+            Err(MediainfoError::NotInstalled) => return Err(ffprobe_error),
+            Err(MediainfoError::TimedOut) => return Err(AppError::Timeout("mediainfo timed out".to_string())),
+            Err(MediainfoError::Failed(e)) => {
+                return Err(AppError::FfprobeFailed(format!("{}\nmediainfo fallback: {}", ffprobe_error, e)));
+            }
+        },
+    };
 
     // Generate a thumbnail for the video and get the path to the generated image.
     // This is also an async operation and may fail.
     let thumbnail_path = None; // Let TypeScript handle thumbnail generation
 
+    let display_size = video_info.display_width.or(video_info.width)
+        .zip(video_info.display_height.or(video_info.height))
+        .filter(|(w, h)| *w > 0 && *h > 0);
+
     // Construct and return the VideoMetadata struct with all collected information.
-    Ok(VideoMetadata {
+    let mut video = VideoMetadata {
         // Create a unique ID by combining the folder and file name.
-        id: format!("{}_{}", folder_name, file_name),
+        id: make_video_id(&folder_name, &file_name),
         // The name of the folder containing the video.
         folder_name,
         // The full path to the video file, converted to a String.
@@ -164,7 +1073,68 @@ async fn extract_video_metadata(path: &std::path::Path) -> Result<VideoMetadata,
         codec: video_info.codec,
         // The path to the generated thumbnail image (if available).
         thumbnail_path,
-    })
+        // The overall and video stream bitrates in bits per second (if available).
+        total_bitrate: video_info.total_bitrate,
+        video_bitrate: video_info.video_bitrate,
+        // Probed on demand, it costs an extra ffprobe run per file.
+        starts_with_keyframe: None,
+        // The rotation and the dimensions as displayed (if available).
+        rotation: video_info.rotation,
+        display_width: video_info.display_width,
+        display_height: video_info.display_height,
+        // Derived from the displayed dimensions.
+        aspect_ratio: display_size.map(|(w, h)| w as f64 / h as f64),
+        resolution_class: display_size.and_then(|(w, h)| ResolutionClass::of(w, h)),
+        // The color description of the video stream (if available).
+        is_hdr: video_info.color_transfer.as_deref().is_some_and(is_hdr_transfer),
+        color_space: video_info.color_space,
+        color_transfer: video_info.color_transfer,
+        color_primaries: video_info.color_primaries,
+        // The codec profile and level (if available).
+        profile: video_info.profile,
+        level: video_info.level,
+        // Derived from the fields above once the struct is complete.
+        decode_difficulty: None,
+        // The recording date from the container tags, formatted like the dates above (if available).
+        media_creation_date: video_info.media_creation_date.map(|millis| millis.to_string()),
+        recorded_date: None,
+        // The software that wrote the file and the recording device (if available).
+        encoder: video_info.encoder,
+        device_model: video_info.device_model,
+        // Generated on demand like the thumbnail.
+        motion_thumbnail_path: None,
+        // The container and audio codec (if available).
+        container_mismatch: container_mismatch(path, video_info.container.as_deref()),
+        container: video_info.container,
+        audio_codec: video_info.audio_codec,
+        // The first audio stream's layout and the number of audio streams.
+        audio_channels: video_info.audio_channels,
+        audio_sample_rate: video_info.audio_sample_rate,
+        audio_track_count: Some(video_info.audio_track_count),
+        has_audio: video_info.audio_track_count > 0,
+        subtitle_tracks: video_info.subtitle_tracks,
+        variable_frame_rate: video_info.variable_frame_rate,
+        // Depends on the configured playback capabilities, filled in by the caller.
+        web_ready: None,
+        // Analysed on demand, it decodes part of the video.
+        motion_type: None,
+        // The tool that produced `video_info`.
+        metadata_source: Some(metadata_source.to_string()),
+        // Hashed on demand, it reads the whole file.
+        content_hash: None,
+        // Decoded on request, see `CrawlOptions::verify`.
+        playable: None,
+        rating: None,
+        is_favorite: false,
+        // Samples the start and end of the file for duplicate detection.
+        quick_hash: content_hash::quick_hash(path).ok(),
+        // Assigned by the database on insert and update.
+        created_at: None,
+        updated_at: None,
+    };
+    video.decode_difficulty = decode_difficulty(&video);
+    video.recorded_date = Some(video.media_creation_date.clone().unwrap_or_else(|| video.creation_date.clone()));
+    Ok(video)
 }
 
 #[derive(Debug)]
@@ -173,24 +1143,146 @@ struct VideoInfo {
     width: Option<u32>,
     height: Option<u32>,
     fps: Option<f32>,
+    variable_frame_rate: bool,
     codec: Option<String>,
+    total_bitrate: Option<u64>,
+    video_bitrate: Option<u64>,
+    rotation: Option<i32>,
+    display_width: Option<u32>,
+    display_height: Option<u32>,
+    profile: Option<String>,
+    level: Option<i32>,
+    /// Epoch millis.
+    media_creation_date: Option<i64>,
+    encoder: Option<String>,
+    device_model: Option<String>,
+    container: Option<String>,
+    audio_codec: Option<String>,
+    audio_channels: Option<u32>,
+    audio_sample_rate: Option<u32>,
+    audio_track_count: u32,
+    subtitle_tracks: Vec<SubtitleTrack>,
+    color_space: Option<String>,
+    color_transfer: Option<String>,
+    color_primaries: Option<String>,
+}
+
+/// Relative difference between the average and nominal frame rate above which a stream counts
+/// as variable frame rate.
+const VFR_TOLERANCE: f32 = 0.01;
+
+/// Parses an ffprobe rate like `30000/1001`. `0/0` and other zero or non-finite rates are `None`.
+fn parse_frame_rate(rate: &str) -> Option<f32> {
+    let (num, den) = rate.split_once('/').unwrap_or((rate, "1"));
+    let fps = num.trim().parse::<f32>().ok()? / den.trim().parse::<f32>().ok()?;
+    (fps.is_finite() && fps > 0.0).then_some(fps)
+}
+
+/// Combines a maker and model tag into one name, avoiding "Apple Apple iPhone" style repeats.
+fn device_name(make: Option<&str>, model: Option<&str>) -> Option<String> {
+    let make = make.map(str::trim).filter(|s| !s.is_empty());
+    let model = model.map(str::trim).filter(|s| !s.is_empty());
+    match (make, model) {
+        (Some(make), Some(model)) if model.to_lowercase().starts_with(&make.to_lowercase()) => Some(model.to_string()),
+        (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+        (make, model) => make.or(model).map(str::to_string),
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Parses an ISO 8601 timestamp as written in container tags, e.g. `2023-05-01T12:34:56.000000Z`
+/// or `2023-05-01 12:34:56+02:00`, to epoch millis. A missing offset is taken as UTC.
+fn parse_iso8601_millis(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let (date, time) = text.split_once(['T', ' ']).unwrap_or((text, "00:00:00"));
+
+    let mut date_parts = date.splitn(3, '-');
+    let year: i64 = date_parts.next()?.parse().ok()?;
+    let month: u32 = date_parts.next()?.parse().ok()?;
+    let day: u32 = date_parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Split off the zone designator: "Z", "+02:00", "-0500" or nothing.
+    let (clock, offset_minutes) = if let Some(clock) = time.strip_suffix(['Z', 'z']) {
+        (clock, 0)
+    } else if let Some(index) = time.rfind(['+', '-']) {
+        let (clock, zone) = time.split_at(index);
+        let sign = if zone.starts_with('-') { -1 } else { 1 };
+        let digits: String = zone[1..].chars().filter(|c| *c != ':').collect();
+        if digits.len() != 4 {
+            return None;
+        }
+        let hours: i64 = digits[..2].parse().ok()?;
+        let minutes: i64 = digits[2..].parse().ok()?;
+        (clock, sign * (hours * 60 + minutes))
+    } else {
+        (time, 0)
+    };
+
+    let mut clock_parts = clock.splitn(3, ':');
+    let hours: i64 = clock_parts.next()?.parse().ok()?;
+    let minutes: i64 = clock_parts.next()?.parse().ok()?;
+    let seconds: f64 = clock_parts.next().unwrap_or("0").parse().ok()?;
+
+    let days = days_from_civil(year, month, day);
+    let millis = ((days * 86_400 + hours * 3_600 + minutes * 60 - offset_minutes * 60) as f64 + seconds) * 1000.0;
+    Some(millis.round() as i64)
+}
+
+/// Reads the display rotation of a video stream, normalized to clockwise degrees in 0..360.
+/// Newer ffprobe builds report it as display matrix side data (counter-clockwise), older ones
+/// as a `rotate` tag (clockwise).
+fn parse_rotation(video_stream: &serde_json::Value) -> Option<i32> {
+    let from_side_data = video_stream["side_data_list"].as_array()
+        .and_then(|list| list.iter().find_map(|side_data| side_data["rotation"].as_f64()))
+        .map(|degrees| -(degrees.round() as i32));
+    let from_tag = || video_stream["tags"]["rotate"].as_str().and_then(|s| s.parse::<i32>().ok());
+    from_side_data.or_else(from_tag).map(|degrees| degrees.rem_euclid(360))
+}
+
+/// Computes the displayed size from the coded size, the sample aspect ratio (e.g. "4:3" for
+/// anamorphic sources, "0:1" when unknown) and the rotation.
+fn display_dimensions(width: u32, height: u32, sample_aspect_ratio: Option<&str>, rotation: Option<i32>) -> (u32, u32) {
+    let sar = sample_aspect_ratio
+        .and_then(|sar| sar.split_once(':'))
+        .and_then(|(num, den)| Some((num.parse::<f64>().ok()?, den.parse::<f64>().ok()?)))
+        .filter(|(num, den)| *num > 0.0 && *den > 0.0)
+        .map(|(num, den)| num / den)
+        .unwrap_or(1.0);
+    let display_width = (width as f64 * sar).round() as u32;
+    match rotation {
+        Some(90) | Some(270) => (height, display_width),
+        _ => (display_width, height),
+    }
 }
 
-async fn extract_ffmpeg_metadata(path: &std::path::Path) -> Result<VideoInfo, String> {
-    // Use ffprobe to get video metadata
-    let output = Command::new("ffprobe")
+async fn extract_ffmpeg_metadata(path: &std::path::Path, timeout: std::time::Duration) -> Result<VideoInfo, AppError> {
+    // Use ffprobe to get video metadata. Some truncated files or stalled network mounts make it
+    // hang, so the child is killed once `timeout` has passed.
+    let mut probe = Command::new(ffmpeg_paths::ffprobe());
+    probe
         .args([
-            "-v", "quiet",
+            "-v", "error",
             "-print_format", "json",
             "-show_format",
             "-show_streams",
-            path.to_str().unwrap()
         ])
-        .output()
-        .map_err(|e| e.to_string())?;
+        .arg(path);
+    let output = output_with_timeout(&mut probe, "ffprobe", timeout).await?;
     
     if !output.status.success() {
-        return Err("ffprobe failed".to_string());
+        return Err(AppError::FfprobeFailed(ffmpeg_failure("ffprobe failed", &String::from_utf8_lossy(&output.stderr))));
     }
     
     let json_str = String::from_utf8(output.stdout).map_err(|e| e.to_string())?;
@@ -208,138 +1300,1818 @@ async fn extract_ffmpeg_metadata(path: &std::path::Path) -> Result<VideoInfo, St
     let width = video_stream["width"].as_u64().map(|w| w as u32);
     let height = video_stream["height"].as_u64().map(|h| h as u32);
     
-    let fps_str = video_stream["r_frame_rate"].as_str().unwrap_or("0/1");
-    let fps = if let Some((num, den)) = fps_str.split_once('/') {
-        let num: f32 = num.parse().unwrap_or(0.0);
-        let den: f32 = den.parse().unwrap_or(1.0);
-        if den != 0.0 { Some(num / den) } else { None }
-    } else {
-        None
+    // r_frame_rate is the stream's base rate and can be a timebase like 90000/1, so the
+    // average rate is preferred. When the two disagree the frame rate is variable.
+    let avg_fps = video_stream["avg_frame_rate"].as_str().and_then(parse_frame_rate);
+    let base_fps = video_stream["r_frame_rate"].as_str().and_then(parse_frame_rate);
+    let fps = avg_fps.or(base_fps);
+    let variable_frame_rate = match (avg_fps, base_fps) {
+        (Some(avg), Some(base)) => (avg - base).abs() / base > VFR_TOLERANCE,
+        _ => false,
     };
     
     let codec = video_stream["codec_name"].as_str().map(|s| s.to_string());
     
+    // ffprobe reports bitrates as decimal strings in bits per second.
+    let total_bitrate = json["format"]["bit_rate"].as_str()
+        .and_then(|s| s.parse::<u64>().ok());
+    let video_bitrate = video_stream["bit_rate"].as_str()
+        .and_then(|s| s.parse::<u64>().ok());
+    
+    let rotation = parse_rotation(video_stream);
+    let (display_width, display_height) = match (width, height) {
+        (Some(w), Some(h)) => {
+            let (dw, dh) = display_dimensions(w, h, video_stream["sample_aspect_ratio"].as_str(), rotation);
+            (Some(dw), Some(dh))
+        }
+        _ => (None, None),
+    };
+    
+    let color = |key: &str| video_stream[key].as_str().filter(|value| *value != "unknown").map(str::to_string);
+    
+    let profile = video_stream["profile"].as_str().map(|s| s.to_string());
+    // ffprobe prints -99 when the level is unknown.
+    let level = video_stream["level"].as_i64()
+        .filter(|level| *level > 0)
+        .map(|level| level as i32);
+    
+    // Muxers without a clock write 1970 or 1904 (the QuickTime epoch); treat those as unset.
+    let media_creation_date = json["format"]["tags"]["creation_time"].as_str()
+        .or_else(|| video_stream["tags"]["creation_time"].as_str())
+        .and_then(parse_iso8601_millis)
+        .filter(|millis| *millis > 0);
+    
+    let container = json["format"]["format_name"].as_str().map(|s| s.to_string());
+    let audio_streams: Vec<&serde_json::Value> = streams.iter()
+        .filter(|s| s["codec_type"] == "audio")
+        .collect();
+    let first_audio = audio_streams.first();
+    let audio_codec = first_audio
+        .and_then(|s| s["codec_name"].as_str())
+        .map(|s| s.to_string());
+    let audio_channels = first_audio
+        .and_then(|s| s["channels"].as_u64())
+        .map(|channels| channels as u32);
+    let audio_sample_rate = first_audio
+        .and_then(|s| s["sample_rate"].as_str())
+        .and_then(|s| s.parse::<u32>().ok());
+    
+    let format_tags = &json["format"]["tags"];
+    let tag = |key: &str| format_tags[key].as_str().map(str::trim).filter(|s| !s.is_empty());
+    let encoder = tag("encoder")
+        .or_else(|| video_stream["tags"]["encoder"].as_str().map(str::trim).filter(|s| !s.is_empty()))
+        .map(str::to_string);
+    let device_model = device_name(tag("com.apple.quicktime.make"), tag("com.apple.quicktime.model"))
+        .or_else(|| device_name(tag("com.android.manufacturer"), tag("com.android.model")))
+        .or_else(|| device_name(tag("make"), tag("model")));
+    
     Ok(VideoInfo {
         duration,
         width,
         height,
         fps,
+        variable_frame_rate,
         codec,
+        total_bitrate,
+        video_bitrate,
+        rotation,
+        display_width,
+        display_height,
+        profile,
+        level,
+        media_creation_date,
+        encoder,
+        device_model,
+        container,
+        audio_codec,
+        audio_channels,
+        audio_sample_rate,
+        audio_track_count: audio_streams.len() as u32,
+        subtitle_tracks: subtitles::subtitle_tracks(streams),
+        color_space: color("color_space"),
+        color_transfer: color("color_transfer"),
+        color_primaries: color("color_primaries"),
     })
 }
 
-// async fn generate_thumbnail(path: &std::path::Path) -> Result<Option<String>, String> {
-//     // Create thumbnails directory
-//     let thumbnails_dir = std::env::temp_dir().join("shadowcrawler_thumbnails");
-//     fs::create_dir_all(&thumbnails_dir).map_err(|e| e.to_string())?;
-    
-//     let thumbnail_name = format!("{}.jpg", 
-//         path.file_stem().unwrap().to_string_lossy()
-//     );
-//     let thumbnail_path = thumbnails_dir.join(thumbnail_name);
-    
-//     // Get video duration first to seek to 10% of the video
-//     let duration_output = Command::new("ffprobe")
-//         .args([
-//             "-v", "quiet",
-//             "-show_entries", "format=duration",
-//             "-of", "csv=p=0",
-//             path.to_str().unwrap()
-//         ])
-//         .output()
-//         .map_err(|e| e.to_string())?;
-    
-//     let duration_str = String::from_utf8(duration_output.stdout)
-//         .map_err(|e| e.to_string())?;
-//     let duration: f64 = duration_str.trim().parse().unwrap_or(0.0);
-//     let seek_time = duration * 0.1; // 10% into the video
-    
-//     // Generate thumbnail using ffmpeg
-//     let output = Command::new("ffmpeg")
-//         .args([
-//             "-i", path.to_str().unwrap(),
-//             "-ss", &format!("{:.2}", seek_time),
-//             "-vframes", "1",
-//             "-s", "320x180",
-//             "-y", // Overwrite output file
-//             thumbnail_path.to_str().unwrap()
-//         ])
-//         .output()
-//         .map_err(|e| e.to_string())?;
-    
-//     if output.status.success() {
-//         Ok(Some(thumbnail_path.to_string_lossy().to_string()))
-//     } else {
-//         eprintln!("FFmpeg error: {}", String::from_utf8_lossy(&output.stderr));
-//         Ok(None)
-//     }
-// }
-
-fn format_timestamp(timestamp: u64) -> String {
-    // Return timestamp in milliseconds since epoch for easy JavaScript parsing
-    (timestamp * 1000).to_string()
-}
-
-#[tauri::command]
-async fn get_thumbnail_data(thumbnail_path: String) -> Result<Vec<u8>, String> {
-    fs::read(&thumbnail_path).map_err(|e| e.to_string())
-}
-
-#[tauri::command]
-async fn get_video_data(video_path: String) -> Result<Vec<u8>, String> {
-    fs::read(&video_path).map_err(|e| e.to_string())
-}
-
-#[tauri::command]
-async fn init_video_database() -> Result<(), String> {
-    // Initialize database - placeholder implementation
-    Ok(())
-}
-
-#[tauri::command]
-async fn get_videos_from_database() -> Result<Vec<VideoMetadata>, String> {
-    // Get videos from database - placeholder implementation
-    Ok(vec![])
+enum MediainfoError {
+    NotInstalled,
+    TimedOut,
+    Failed(String),
 }
 
-#[tauri::command]
-async fn get_videos_by_folder(_folder_name: String) -> Result<Vec<VideoMetadata>, String> {
-    // Get videos by folder - placeholder implementation
-    Ok(vec![])
+/// Translates a MediaInfo format name into the ffprobe codec or container name stored for it,
+/// so that fallback rows compare and filter like the rest.
+fn ffprobe_name(mediainfo_format: &str, format_profile: Option<&str>) -> String {
+    let name = match mediainfo_format {
+        // Video codecs.
+        "AVC" => "h264",
+        "HEVC" => "hevc",
+        "MPEG-4 Visual" => "mpeg4",
+        "MPEG Video" => "mpeg2video",
+        "VC-1" => "vc1",
+        "ProRes" => "prores",
+        // Audio codecs.
+        "MPEG Audio" => match format_profile {
+            Some("Layer 2") => "mp2",
+            _ => "mp3",
+        },
+        "AC-3" => "ac3",
+        "E-AC-3" => "eac3",
+        "PCM" => "pcm_s16le",
+        // Containers, as ffprobe's `format_name`.
+        "MPEG-4" | "QuickTime" => "mov,mp4,m4a,3gp,3g2,mj2",
+        "Matroska" | "WebM" => "matroska,webm",
+        "Flash Video" => "flv",
+        "Windows Media" => "asf",
+        "MPEG-TS" => "mpegts",
+        // Most others (VP8, VP9, AV1, AAC, Opus, AVI, ...) only differ in case.
+        other => return other.to_lowercase(),
+    };
+    name.to_string()
 }
 
-#[tauri::command]
-async fn get_folders() -> Result<Vec<String>, String> {
-    // Get folders - placeholder implementation
-    Ok(vec![])
+/// Compares ffprobe's `format_name` with what the file extension promises. MP4 and QuickTime
+/// share a demuxer, so `.mp4` and `.mov` are not told apart.
+fn container_mismatch(path: &std::path::Path, container: Option<&str>) -> Option<bool> {
+    let container = container?;
+    let expected: &[&str] = match path.extension()?.to_string_lossy().to_lowercase().as_str() {
+        "mp4" | "m4v" | "mov" => &["mp4", "mov"],
+        "mkv" => &["matroska"],
+        "webm" => &["webm"],
+        "avi" => &["avi"],
+        "flv" => &["flv"],
+        "wmv" => &["asf"],
+        "ts" | "mts" | "m2ts" => &["mpegts"],
+        _ => return None,
+    };
+    Some(!container.split(',').any(|name| expected.contains(&name)))
 }
 
-#[tauri::command]
-async fn insert_video_record(_video: VideoMetadata) -> Result<(), String> {
-    // Insert video record - placeholder implementation
-    Ok(())
+/// Translates a MediaInfo color description ("BT.2020", "PQ", "BT.2020 non-constant") into the
+/// name ffprobe reports for it.
+fn ffprobe_color_name(name: &str) -> String {
+    match name {
+        "PQ" => "smpte2084".to_string(),
+        "HLG" => "arib-std-b67".to_string(),
+        "BT.2020 non-constant" => "bt2020nc".to_string(),
+        "BT.2020 constant" => "bt2020c".to_string(),
+        _ => name.to_lowercase().replace(['.', ' '], ""),
+    }
 }
 
+/// Fallback for files ffprobe can't read: the same fields from `mediainfo --Output=JSON`, which
+/// reports every value as a string. Killed like ffprobe once `timeout` has passed.
+async fn extract_mediainfo_metadata(path: &std::path::Path, timeout: std::time::Duration) -> Result<VideoInfo, MediainfoError> {
+    let run = Command::new("mediainfo").arg("--Output=JSON").arg(path).kill_on_drop(true).output();
+    let output = match tokio::time::timeout(timeout, run).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::NotFound => return Err(MediainfoError::NotInstalled),
+        Ok(Err(e)) => return Err(MediainfoError::Failed(e.to_string())),
+        Err(_) => return Err(MediainfoError::TimedOut),
+    };
+    if !output.status.success() {
+        return Err(MediainfoError::Failed(stderr_tail(&String::from_utf8_lossy(&output.stderr), FFMPEG_ERROR_TAIL_LINES)));
+    }
+    
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| MediainfoError::Failed(e.to_string()))?;
+    let tracks = json["media"]["track"].as_array()
+        .ok_or_else(|| MediainfoError::Failed("No tracks found".to_string()))?;
+    let track = |kind: &str| tracks.iter().find(|t| t["@type"] == kind);
+    let general = track("General").unwrap_or(&serde_json::Value::Null);
+    let video = track("Video").ok_or_else(|| MediainfoError::Failed("No video track found".to_string()))?;
+    let audio = track("Audio");
+    
+    let text = |track: &serde_json::Value, key: &str| track[key].as_str().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+    let number = |track: &serde_json::Value, key: &str| text(track, key).and_then(|s| s.parse::<f64>().ok());
+    
+    let width = number(video, "Width").map(|w| w as u32);
+    let height = number(video, "Height").map(|h| h as u32);
+    let codec = text(video, "Format").map(|format| ffprobe_name(&format, None));
+    let rotation = number(video, "Rotation").map(|degrees| (degrees.round() as i32).rem_euclid(360));
+    let (display_width, display_height) = match (width, height) {
+        (Some(w), Some(h)) => {
+            let sar = number(video, "PixelAspectRatio").map(|par| format!("{}:1", par));
+            let (dw, dh) = display_dimensions(w, h, sar.as_deref(), rotation);
+            (Some(dw), Some(dh))
+        }
+        _ => (None, None),
+    };
+    
+    // Levels are stored the way ffprobe reports them: 4.1 is 41 for H.264 and 123 for HEVC.
+    let level = number(video, "Format_Level").and_then(|level| match codec.as_deref() {
+        Some("h264") => Some((level * 10.0).round() as i32),
+        Some("hevc") => Some((level * 30.0).round() as i32),
+        _ => None,
+    });
+    
+    // Dates look like "2023-05-01 12:34:56 UTC" (older versions put the zone first).
+    let media_creation_date = text(general, "Encoded_Date")
+        .or_else(|| text(general, "Tagged_Date"))
+        .and_then(|date| parse_iso8601_millis(date.replace("UTC", "").trim()))
+        .filter(|millis| *millis > 0);
+    
+    Ok(VideoInfo {
+        duration: number(general, "Duration"),
+        width,
+        height,
+        fps: number(video, "FrameRate").map(|fps| fps as f32).filter(|fps| fps.is_finite() && *fps > 0.0),
+        variable_frame_rate: text(video, "FrameRate_Mode").as_deref() == Some("VFR"),
+        codec,
+        total_bitrate: number(general, "OverallBitRate").map(|b| b as u64),
+        video_bitrate: number(video, "BitRate").map(|b| b as u64),
+        rotation,
+        display_width,
+        display_height,
+        profile: text(video, "Format_Profile"),
+        level,
+        media_creation_date,
+        encoder: text(general, "Encoded_Application"),
+        device_model: device_name(
+            text(general, "Encoded_Hardware_CompanyName").as_deref(),
+            text(general, "Encoded_Hardware_Name").as_deref(),
+        ),
+        container: text(general, "Format").map(|format| ffprobe_name(&format, None)),
+        audio_codec: audio.and_then(|audio| {
+            let format = text(audio, "Format")?;
+            Some(ffprobe_name(&format, text(audio, "Format_Profile").as_deref()))
+        }),
+        audio_channels: audio.and_then(|audio| number(audio, "Channels")).map(|channels| channels as u32),
+        audio_sample_rate: audio.and_then(|audio| number(audio, "SamplingRate")).map(|rate| rate as u32),
+        audio_track_count: tracks.iter().filter(|t| t["@type"] == "Audio").count() as u32,
+        // MediaInfo doesn't report the stream indices `subtitles::extract_subtitles` needs.
+        subtitle_tracks: Vec::new(),
+        color_space: text(video, "matrix_coefficients").map(|name| ffprobe_color_name(&name)),
+        color_transfer: text(video, "transfer_characteristics").map(|name| ffprobe_color_name(&name)),
+        color_primaries: text(video, "colour_primaries").map(|name| ffprobe_color_name(&name)),
+    })
+}
+
+/// Result of inspecting the first few seconds of a file's video packets.
+struct KeyframeProbe {
+    starts_with_keyframe: bool,
+    /// Presentation time of the first keyframe, if one occurs within the probed window.
+    first_keyframe_time: Option<f64>,
+}
+
+/// Reads the video packets of the first 10 seconds and reports whether the stream opens
+/// with a keyframe and where the first one is.
+async fn probe_leading_keyframe(path: &std::path::Path) -> Result<KeyframeProbe, AppError> {
+    let mut probe = Command::new(ffmpeg_paths::ffprobe());
+    probe.args([
+        "-v", "error",
+        "-select_streams", "v:0",
+        "-read_intervals", "%+10",
+        "-show_entries", "packet=pts_time,flags",
+        "-print_format", "json",
+    ]);
+    probe.arg(path);
+    let output = output_with_timeout(&mut probe, "ffprobe", DEFAULT_PROBE_TIMEOUT).await?;
+    
+    if !output.status.success() {
+        return Err(AppError::FfprobeFailed(ffmpeg_failure("ffprobe failed", &String::from_utf8_lossy(&output.stderr))));
+    }
+    
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).map_err(|e| e.to_string())?;
+    let packets = json["packets"].as_array().ok_or("No video packets found")?;
+    let is_keyframe = |packet: &&serde_json::Value| packet["flags"].as_str().is_some_and(|f| f.contains('K'));
+    
+    let first_packet = packets.first().ok_or("No video packets found")?;
+    let first_keyframe_time = packets.iter()
+        .find(is_keyframe)
+        .and_then(|p| p["pts_time"].as_str())
+        .and_then(|t| t.parse::<f64>().ok());
+    
+    Ok(KeyframeProbe {
+        starts_with_keyframe: is_keyframe(&first_packet),
+        first_keyframe_time,
+    })
+}
+
+/// How `generate_thumbnail` picks the frame to extract.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum ThumbnailStrategy {
+    /// The frame at `seek_percent` of the duration.
+    #[default]
+    FixedPercent,
+    /// The first scene cut after the start of the video.
+    SceneChange,
+    /// The best-scoring of several sampled frames, favouring detail, motion and sane exposure.
+    MostInteresting,
+}
+
+/// Number of frames sampled by `ThumbnailStrategy::MostInteresting`.
+const INTERESTING_FRAME_CANDIDATES: usize = 6;
+
+/// `(pts_time, key -> value)` metadata of one frame, as printed by ffmpeg's `metadata` filter.
+type FrameMetadata = (f64, std::collections::HashMap<String, String>);
+
+/// Parses the output of ffmpeg's `metadata=print:file=-` filter into one entry per frame.
+fn parse_frame_metadata(output: &str) -> Vec<FrameMetadata> {
+    let mut frames = Vec::new();
+    for line in output.lines() {
+        if line.starts_with("frame:") {
+            let pts_time = line.split_whitespace()
+                .find_map(|field| field.strip_prefix("pts_time:"))
+                .and_then(|t| t.parse().ok())
+                .unwrap_or(0.0);
+            frames.push((pts_time, std::collections::HashMap::new()));
+        } else if let (Some((key, value)), Some((_, entries))) = (line.split_once('='), frames.last_mut()) {
+            entries.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    frames
+}
+
+/// Runs ffmpeg with `filter` on at most `max_frames` output frames of `path` (discarding the
+/// output) and returns the per-frame metadata.
+async fn run_metadata_filter(path: &std::path::Path, input_args: &[String], filter: &str, max_frames: u32) -> Result<Vec<FrameMetadata>, AppError> {
+    let mut analysis = Command::new(ffmpeg_paths::ffmpeg());
+    analysis.args(["-hide_banner", "-v", "error"])
+        .args(input_args)
+        .arg("-i")
+        .arg(path)
+        .args(["-an", "-vf", filter, "-frames:v", &max_frames.to_string(), "-f", "null", "-"]);
+    let output = output_with_timeout(&mut analysis, "ffmpeg", FRAME_ANALYSIS_TIMEOUT).await?;
+    
+    if !output.status.success() {
+        return Err(AppError::FfmpegFailed(ffmpeg_failure("Frame analysis failed", &String::from_utf8_lossy(&output.stderr))));
+    }
+    
+    Ok(parse_frame_metadata(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Finds the first scene cut within 60 seconds after the 5% mark.
+async fn find_scene_change(path: &std::path::Path, duration: f64) -> Option<f64> {
+    let start = duration * 0.05;
+    let input_args = ["-ss".to_string(), format!("{:.2}", start), "-t".to_string(), "60".to_string()];
+    let frames = run_metadata_filter(path, &input_args, "select='gt(scene,0.3)',metadata=print:file=-", 1).await.ok()?;
+    // Input seeking resets timestamps, so the reported time is relative to `start`.
+    frames.first().map(|(pts_time, _)| start + pts_time)
+}
+
+/// Scores a frame from its signalstats/entropy metadata: detail (entropy), motion against the
+/// previous frame (YDIF) and exposure, where near-black and blown-out frames score lowest.
+fn score_frame(entries: &std::collections::HashMap<String, String>) -> f64 {
+    let value = |key: &str| entries.get(key).and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0);
+    let detail = value("lavfi.entropy.normalized_entropy.normal.Y");
+    let motion = (value("lavfi.signalstats.YDIF") / 32.0).min(1.0);
+    let brightness = value("lavfi.signalstats.YAVG") / 255.0;
+    let exposure = 1.0 - ((brightness - 0.5).abs() * 2.0).powi(2);
+    (detail * 0.6 + motion * 0.4) * exposure
+}
+
+/// Samples evenly spaced candidate frames between 10% and 90% of the duration and returns the
+/// time of the highest scoring one.
+async fn find_most_interesting_frame(path: &std::path::Path, duration: f64) -> Option<f64> {
+    let mut scores = Vec::with_capacity(INTERESTING_FRAME_CANDIDATES);
+    for i in 0..INTERESTING_FRAME_CANDIDATES {
+        let time = duration * (0.1 + 0.8 * i as f64 / (INTERESTING_FRAME_CANDIDATES - 1) as f64);
+        // Two frames, so signalstats can report the difference to the previous one.
+        let input_args = ["-ss".to_string(), format!("{:.2}", time)];
+        let Ok(frames) = run_metadata_filter(
+            path,
+            &input_args,
+            "scale=160:-2,signalstats,entropy,metadata=print:file=-",
+            2,
+        ).await else { continue };
+        if let Some((_, entries)) = frames.into_iter().next_back() {
+            scores.push((time, score_frame(&entries)));
+        }
+    }
+    scores.into_iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(time, _)| time)
+}
+
+/// Directory generated thumbnails are written to, created if needed.
+fn thumbnails_dir() -> Result<std::path::PathBuf, AppError> {
+    let dir = std::env::temp_dir().join("shadowcrawler_thumbnails");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Path in `thumbnails_dir` for an image generated from `video_path`, e.g. `clip_3f2a...jpg` for
+/// `suffix` ".jpg". The name carries a hash of the normalized path, size and modification time,
+/// so same-named videos in different folders don't collide and a changed file gets a new image.
+fn thumbnail_path_for(video_path: &std::path::Path, suffix: &str) -> Result<std::path::PathBuf, AppError> {
+    use sha2::{Digest, Sha256};
+    
+    let metadata = fs::metadata(video_path)?;
+    let mut hasher = Sha256::new();
+    hasher.update(database::normalize_path(&video_path.to_string_lossy()).as_bytes());
+    hasher.update(metadata.len().to_le_bytes());
+    hasher.update(modified_timestamp(&metadata).as_bytes());
+    let hash: String = hasher.finalize()[..8].iter().map(|byte| format!("{:02x}", byte)).collect();
+    
+    let stem = video_path.file_stem()
+        .ok_or_else(|| AppError::InvalidInput(format!("{} has no file name", video_path.display())))?
+        .to_string_lossy();
+    Ok(thumbnails_dir()?.join(format!("{}_{}{}", stem, hash, suffix)))
+}
+
+/// Maps HDR frames (PQ or HLG, BT.2020) to SDR BT.709 so previews don't look washed out.
+const HDR_TONEMAP_FILTER: &str = "zscale=t=linear:npl=100,format=gbrpf32le,zscale=p=bt709,\
+    tonemap=tonemap=hable:desat=0,zscale=t=bt709:m=bt709:r=tv,format=yuv420p";
+
+/// `HDR_TONEMAP_FILTER` if the installed ffmpeg has the filters it needs (zscale comes with
+/// builds linked against zimg). Checked once per run, so batch thumbnailing doesn't list the
+/// filters for every file; a failed check is retried next time.
+async fn hdr_tonemap_filter() -> Option<&'static str> {
+    static SUPPORTED: tokio::sync::OnceCell<bool> = tokio::sync::OnceCell::const_new();
+    let supported = SUPPORTED.get_or_try_init(|| async {
+        let filters = ffmpeg_listing("-filters").await?;
+        Ok::<_, AppError>(filters.contains("zscale") && filters.contains("tonemap"))
+    }).await.ok()?;
+    supported.then_some(HDR_TONEMAP_FILTER)
+}
+
+/// Where `generate_thumbnail` takes the frame from when the duration is zero or unknown.
+const UNKNOWN_DURATION_SEEK_SECONDS: f64 = 1.0;
+
+/// Extracts a single frame of `video_path` into the temp thumbnails directory and returns its path.
+/// Frames of videos stored as HDR are tone mapped to SDR when ffmpeg supports it.
+///
+/// # Arguments
+/// * `seek_percent` - Position of the frame as a percentage of the duration (default 10%). When
+///   the duration is zero or unknown, the frame is taken `UNKNOWN_DURATION_SEEK_SECONDS` in.
+/// * `size` - Output `(width, height)` in pixels (default 320x180).
+/// * `strategy` - How the frame is chosen (default `FixedPercent`). The content-aware strategies
+///   fall back to `seek_percent` when they find nothing.
+#[tauri::command]
+async fn generate_thumbnail(app: AppHandle, db: State<'_, Database>, video_path: String, seek_percent: Option<f64>, size: Option<(u32, u32)>, strategy: Option<ThumbnailStrategy>) -> Result<String, AppError> {
+    let path = std::path::Path::new(&video_path);
+    
+    let thumbnail_path = thumbnail_path_for(path, ".jpg")?;
+    
+    // Get video duration first to seek into the video
+    let mut probe = Command::new(ffmpeg_paths::ffprobe());
+    probe
+        .args([
+            "-v", "quiet",
+            "-show_entries", "format=duration",
+            "-of", "csv=p=0",
+        ])
+        .arg(path);
+    let duration_output = output_with_timeout(&mut probe, "ffprobe", DEFAULT_PROBE_TIMEOUT).await?;
+    
+    let duration_str = String::from_utf8(duration_output.stdout)
+        .map_err(|e| e.to_string())?;
+    let duration: f64 = duration_str.trim().parse().ok().filter(|d: &f64| d.is_finite() && *d > 0.0).unwrap_or(0.0);
+    let fixed_time = if duration > 0.0 {
+        duration * seek_percent.unwrap_or(10.0) / 100.0
+    } else {
+        UNKNOWN_DURATION_SEEK_SECONDS
+    };
+    let mut seek_time = match strategy.unwrap_or_default() {
+        ThumbnailStrategy::FixedPercent => None,
+        ThumbnailStrategy::SceneChange => find_scene_change(path, duration).await,
+        ThumbnailStrategy::MostInteresting => find_most_interesting_frame(path, duration).await,
+    }.unwrap_or(fixed_time);
+    
+    // Decoding from before the first keyframe yields a grey or broken frame, so never seek
+    // earlier than the first keyframe.
+    if let Ok(KeyframeProbe { first_keyframe_time: Some(keyframe_time), .. }) = probe_leading_keyframe(path).await {
+        seek_time = seek_time.max(keyframe_time);
+    }
+    
+    let (mut width, mut height) = size.unwrap_or((320, 180));
+    
+    // ffmpeg applies the display rotation while decoding, so for portrait phone videos the
+    // requested box is turned as well instead of squashing the upright frame into it.
+    let (rotation, is_hdr): (Option<i32>, bool) = db.conn().query_row(
+        "SELECT rotation, is_hdr FROM videos WHERE full_path = ?1",
+        [database::normalize_path(&video_path)],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).unwrap_or((None, false));
+    if matches!(rotation, Some(90 | 270)) {
+        std::mem::swap(&mut width, &mut height);
+    }
+    
+    // Generate thumbnail using ffmpeg
+    let mut command = Command::new(ffmpeg_paths::ffmpeg());
+    command.args(["-ss", &format!("{:.2}", seek_time), "-i"]).arg(path);
+    if is_hdr {
+        if let Some(filter) = hdr_tonemap_filter().await {
+            command.args(["-vf", filter]);
+        }
+    }
+    command
+        .args([
+            "-vframes", "1",
+            "-s", &format!("{}x{}", width, height),
+            "-y", // Overwrite output file
+        ])
+        .arg(&thumbnail_path);
+    let output = output_with_timeout(&mut command, "ffmpeg", FRAME_ANALYSIS_TIMEOUT).await?;
+    
+    if !output.status.success() {
+        return Err(AppError::FfmpegFailed(ffmpeg_failure("Thumbnail generation failed", &String::from_utf8_lossy(&output.stderr))));
+    }
+    
+    let thumbnail_path = database::normalize_path(&thumbnail_path.to_string_lossy());
+    db.conn().execute(
+        "UPDATE videos SET thumbnail_path = ?1 WHERE full_path = ?2",
+        [&thumbnail_path, &database::normalize_path(&video_path)],
+    )?;
+    thumbnail_cache::enforce_limit_in_background(&app);
+    
+    Ok(thumbnail_path)
+}
+
+/// Payload of the "thumbnail-progress" event sent for each file by `generate_thumbnails_since`.
+#[derive(Debug, Serialize, Clone)]
+pub struct ThumbnailProgress {
+    pub processed: u64,
+    pub total: u64,
+    pub full_path: String,
+    pub thumbnail_path: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Generates default thumbnails for the videos inserted after `timestamp` (epoch millis) that
+/// don't have one yet, e.g. after an incremental index, sending a "thumbnail-progress" event to
+/// `window` for each. Returns the number of thumbnails generated.
+#[tauri::command]
+async fn generate_thumbnails_since(app: AppHandle, window: tauri::Window, db: State<'_, Database>, timestamp: i64) -> Result<u64, AppError> {
+    let videos = database::query_videos(
+        &db.conn(),
+        "WHERE created_at > ?1 AND thumbnail_path IS NULL ORDER BY created_at",
+        [timestamp],
+    )?;
+    let total = videos.len() as u64;
+    let mut throttle = power::BatteryThrottle::new(power::battery_policy(&app));
+    
+    let mut generated = 0;
+    for (index, video) in videos.into_iter().enumerate() {
+        let started = std::time::Instant::now();
+        let (thumbnail_path, error) = match generate_thumbnail(app.clone(), db.clone(), video.full_path.clone(), None, None, None).await {
+            Ok(thumbnail_path) => {
+                generated += 1;
+                (Some(thumbnail_path), None)
+            }
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let _ = window.emit("thumbnail-progress", ThumbnailProgress {
+            processed: index as u64 + 1,
+            total,
+            full_path: video.full_path,
+            thumbnail_path,
+            error,
+        });
+        throttle.pace(started.elapsed(), || false).await;
+    }
+    
+    Ok(generated)
+}
+
+/// Upper bound on the frames compared for a motion thumbnail; long clips are sampled more sparsely.
+const MOTION_SAMPLE_FRAMES: f64 = 300.0;
+
+/// Renders one image summarizing the motion in a clip: the last frame, darkened, with every
+/// region that changed at any point painted red (brighter for stronger change). Frames are
+/// sampled at up to 2 per second, differenced against the previous sample, and the differences
+/// kept as a running per-pixel maximum. Stored as the video's `motion_thumbnail_path`.
+#[tauri::command]
+async fn generate_motion_thumbnail(app: AppHandle, db: State<'_, Database>, video_id: String) -> Result<String, AppError> {
+    let video = database::video_by_id(&db.conn(), &video_id)?;
+    let path = std::path::Path::new(&video.full_path);
+    let duration = video.duration.filter(|d| *d > 0.0).ok_or("Video has no known duration")?;
+    
+    let thumbnail_path = thumbnail_path_for(path, "_motion.jpg")?;
+    
+    let sample_rate = (MOTION_SAMPLE_FRAMES / duration).min(2.0);
+    // The lut amplifies small differences and cuts sensor noise (differences below 8).
+    let filter = format!(
+        "fps={:.4},scale=320:-2,split[base][motion];\
+         [motion]format=gray,tblend=all_mode=difference,lut=c0='if(lt(val,8),0,min(255,val*4))',\
+         lagfun=decay=1,format=gbrp,colorchannelmixer=gg=0:bb=0[heat];\
+         [base]eq=brightness=-0.25:saturation=0.3,format=gbrp[dim];\
+         [dim][heat]blend=all_mode=addition",
+        sample_rate,
+    );
+    
+    // `-update 1` keeps overwriting the single output image, leaving the last, fully accumulated frame.
+    let mut render = Command::new(ffmpeg_paths::ffmpeg());
+    render.arg("-i")
+        .arg(path)
+        .args(["-an", "-filter_complex", &filter, "-update", "1", "-y"])
+        .arg(&thumbnail_path);
+    let output = output_with_timeout(&mut render, "ffmpeg", full_decode_timeout(Some(duration))).await?;
+    
+    if !output.status.success() {
+        return Err(AppError::FfmpegFailed(ffmpeg_failure("Motion thumbnail generation failed", &String::from_utf8_lossy(&output.stderr))));
+    }
+    
+    let thumbnail_path = database::normalize_path(&thumbnail_path.to_string_lossy());
+    db.conn().execute(
+        "UPDATE videos SET motion_thumbnail_path = ?1 WHERE full_path = ?2",
+        [&thumbnail_path, &video.full_path],
+    )?;
+    thumbnail_cache::enforce_limit_in_background(&app);
+    
+    Ok(thumbnail_path)
+}
+
+/// Lists videos whose stream doesn't open with a keyframe (they thumbnail and seek badly, and are
+/// candidates for a web re-encode). Rows not checked yet are probed and the result is stored.
+#[tauri::command]
+async fn find_videos_without_leading_keyframe(db: State<'_, Database>, folder_name: Option<String>) -> Result<Vec<VideoMetadata>, AppError> {
+    let unchecked = database::query_videos(
+        &db.conn(),
+        "WHERE starts_with_keyframe IS NULL AND (?1 IS NULL OR folder_name = ?1)",
+        [&folder_name],
+    )?;
+    
+    for video in unchecked {
+        match probe_leading_keyframe(std::path::Path::new(&video.full_path)).await {
+            Ok(probe) => {
+                db.conn().execute(
+                    "UPDATE videos SET starts_with_keyframe = ?1 WHERE full_path = ?2",
+                    rusqlite::params![probe.starts_with_keyframe, video.full_path],
+                )?;
+            }
+            Err(e) => log::warn!("Error probing keyframes of {}: {}", video.full_path, e),
+        }
+    }
+    
+    database::query_videos(
+        &db.conn(),
+        "WHERE starts_with_keyframe = 0 AND (?1 IS NULL OR folder_name = ?1) ORDER BY full_path",
+        [&folder_name],
+    )
+}
+
+fn format_timestamp(timestamp: u64) -> String {
+    // Return timestamp in milliseconds since epoch for easy JavaScript parsing
+    (timestamp * 1000).to_string()
+}
+
+/// How the dates of a `VideoMetadata` are written. All three are read back by
+/// `insert_video_record` and the other commands that store videos.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum TimestampFormat {
+    /// Epoch milliseconds, e.g. "1717251725000", as `format_timestamp` produces.
+    #[default]
+    RawMillis,
+    /// UTC, e.g. "2024-06-01T14:22:05Z".
+    Iso8601,
+    /// UTC, e.g. "Sat, 1 Jun 2024 14:22:05 +0000".
+    Rfc2822,
+}
+
+impl TimestampFormat {
+    /// Writes the epoch-millis string `millis` in this format. Other strings are kept as they are.
+    fn apply(self, millis: &str) -> String {
+        let Some(time) = millis.parse().ok().and_then(chrono::DateTime::from_timestamp_millis) else {
+            return millis.to_string();
+        };
+        match self {
+            TimestampFormat::RawMillis => millis.to_string(),
+            TimestampFormat::Iso8601 => time.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true),
+            TimestampFormat::Rfc2822 => time.to_rfc2822(),
+        }
+    }
+}
+
+impl VideoMetadata {
+    /// Rewrites the dates, which extraction and the database produce as epoch millis, in `format`.
+    fn set_timestamp_format(&mut self, format: TimestampFormat) {
+        self.creation_date = format.apply(&self.creation_date);
+        self.modified_date = format.apply(&self.modified_date);
+        for date in [&mut self.media_creation_date, &mut self.recorded_date].into_iter().flatten() {
+            *date = format.apply(date);
+        }
+    }
+}
+
+/// Reads a generated thumbnail, marking it as recently used for the cache limit.
+#[tauri::command]
+async fn get_thumbnail_data(db: State<'_, Database>, thumbnail_path: String) -> Result<Vec<u8>, AppError> {
+    let data = fs::read(&thumbnail_path)?;
+    thumbnail_cache::touch(&db.conn(), &thumbnail_path)?;
+    Ok(data)
+}
+
+#[tauri::command]
+async fn get_video_data(video_path: String) -> Result<Vec<u8>, AppError> {
+    Ok(fs::read(&video_path)?)
+}
+
+/// Creates any missing tables and columns. The database is already opened and initialized at
+/// startup, so this is only needed after the file was replaced while the app was running.
+#[tauri::command]
+async fn init_video_database(db: State<'_, Database>) -> Result<(), AppError> {
+    Ok(database::init_schema(&db.conn())?)
+}
+
+#[tauri::command]
+async fn get_videos_from_database(db: State<'_, Database>) -> Result<Vec<VideoMetadata>, AppError> {
+    database::query_videos(&db.conn(), "ORDER BY full_path", [])
+}
+
+/// The stored videos matching `filter`, e.g. `{ "min_rating": 4 }` or `{ "favorites_only": true }`,
+/// ordered by path.
+#[tauri::command]
+async fn get_videos(db: State<'_, Database>, filter: database::VideoFilter) -> Result<Vec<VideoMetadata>, AppError> {
+    let (clause, values) = filter.to_sql();
+    database::query_videos(
+        &db.conn(),
+        &format!("{} ORDER BY full_path", clause),
+        rusqlite::params_from_iter(values),
+    )
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct VideoPage {
+    pub videos: Vec<VideoMetadata>,
+    /// Number of videos in the whole library.
+    pub total: u64,
+}
+
+/// `limit` videos starting at `offset` in the order given by `sort_by`, for virtual scrolling
+/// without sending the whole library at once.
+#[tauri::command]
+async fn get_videos_paged(
+    db: State<'_, Database>,
+    offset: u64,
+    limit: u64,
+    sort_by: database::SortField,
+    descending: bool,
+) -> Result<VideoPage, AppError> {
+    let conn = db.conn();
+    let total = conn.query_row("SELECT COUNT(*) FROM videos", [], |row| row.get(0))?;
+    let videos = database::query_videos(
+        &conn,
+        &format!("ORDER BY {} LIMIT ?1 OFFSET ?2", sort_by.order_by(descending)),
+        rusqlite::params![limit.min(i64::MAX as u64) as i64, offset.min(i64::MAX as u64) as i64],
+    )?;
+    Ok(VideoPage { videos, total })
+}
+
+/// Number of results `search_videos` returns by default.
+const DEFAULT_SEARCH_LIMIT: u32 = 100;
+
+/// The best `limit` matches of `query` in file and folder names, most relevant first. Each word
+/// must appear, the last one may be the start of a word.
+#[tauri::command]
+async fn search_videos(db: State<'_, Database>, query: String, limit: Option<u32>) -> Result<Vec<VideoMetadata>, AppError> {
+    let Some(query) = database::search_query(&query) else {
+        return Ok(Vec::new());
+    };
+    database::query_videos(
+        &db.conn(),
+        "JOIN (
+            SELECT rowid AS match_rowid, rank AS match_rank FROM videos_fts
+            WHERE videos_fts MATCH ?1 ORDER BY rank LIMIT ?2
+         ) ON match_rowid = videos.rowid
+         ORDER BY match_rank",
+        rusqlite::params![query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT)],
+    )
+}
+
+/// Highest star rating.
+const MAX_RATING: u8 = 5;
+
+/// Rates `video_id` with 1 to `MAX_RATING` stars; 0 clears the rating. Ratings survive
+/// re-indexing.
+#[tauri::command]
+async fn set_rating(db: State<'_, Database>, video_id: String, rating: u8) -> Result<(), AppError> {
+    if rating > MAX_RATING {
+        return Err(AppError::InvalidInput(format!("Rating must be between 0 and {}, got {}", MAX_RATING, rating)));
+    }
+    let conn = db.conn();
+    let video = database::video_by_id(&conn, &video_id)?;
+    conn.execute(
+        "UPDATE videos SET rating = ?1 WHERE full_path = ?2",
+        rusqlite::params![(rating > 0).then_some(rating), video.full_path],
+    )?;
+    Ok(())
+}
+
+/// Marks `video_id` as a favorite, or unmarks it if it already is one. Returns the new state.
+#[tauri::command]
+async fn toggle_favorite(db: State<'_, Database>, video_id: String) -> Result<bool, AppError> {
+    let conn = db.conn();
+    let video = database::video_by_id(&conn, &video_id)?;
+    conn.execute(
+        "UPDATE videos SET is_favorite = ?1 WHERE full_path = ?2",
+        rusqlite::params![!video.is_favorite, video.full_path],
+    )?;
+    Ok(!video.is_favorite)
+}
+
+/// The stored videos of one folder, ordered by file name.
+#[tauri::command]
+async fn get_videos_by_folder(db: State<'_, Database>, folder_name: String) -> Result<Vec<VideoMetadata>, AppError> {
+    database::query_videos(
+        &db.conn(),
+        "WHERE folder_name = ?1 ORDER BY file_name",
+        [&folder_name],
+    )
+}
+
+/// Lists videos whose container doesn't match their extension, which browsers often refuse to
+/// play despite the familiar name.
+#[tauri::command]
+async fn find_container_mismatches(db: State<'_, Database>) -> Result<Vec<VideoMetadata>, AppError> {
+    database::query_videos(&db.conn(), "WHERE container_mismatch = 1 ORDER BY full_path", [])
+}
+
+/// Lists videos by bitrate, highest first, to surface the files worth re-encoding. Limited to
+/// `folder_name` when given. The video stream bitrate is used where known, the overall one
+/// otherwise; videos with neither are left out.
+#[tauri::command]
+async fn get_videos_by_bitrate(db: State<'_, Database>, folder_name: Option<String>) -> Result<Vec<VideoMetadata>, AppError> {
+    database::query_videos(
+        &db.conn(),
+        "WHERE COALESCE(video_bitrate, total_bitrate) IS NOT NULL
+           AND (?1 IS NULL OR folder_name = ?1)
+         ORDER BY COALESCE(video_bitrate, total_bitrate) DESC, full_path",
+        [folder_name],
+    )
+}
+
+/// Groups the videos recorded in `month` (1-12) of `year` by day of the month, for a calendar view.
+/// Uses the embedded recording date where known and the filesystem creation date otherwise,
+/// both interpreted in local time. Days without videos are left out.
+#[tauri::command]
+async fn get_videos_by_day(db: State<'_, Database>, year: i32, month: u32) -> Result<std::collections::BTreeMap<u32, Vec<VideoMetadata>>, AppError> {
+    if !(1..=12).contains(&month) {
+        return Err(AppError::InvalidInput(format!("Invalid month: {}", month)));
+    }
+
+    let conn = db.conn();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, CAST(strftime('%d', recorded_date / 1000, 'unixepoch', 'localtime') AS INTEGER) AS day
+         FROM videos
+         WHERE strftime('%Y-%m', recorded_date / 1000, 'unixepoch', 'localtime') = ?1
+         ORDER BY recorded_date",
+        database::VIDEO_COLUMNS,
+    ))?;
+
+    let rows = stmt.query_map([format!("{:04}-{:02}", year, month)], |row| {
+        Ok((row.get::<_, u32>("day")?, database::row_to_video(row)?))
+    })?;
+
+    let mut days: std::collections::BTreeMap<u32, Vec<VideoMetadata>> = std::collections::BTreeMap::new();
+    for row in rows {
+        let (day, video) = row?;
+        days.entry(day).or_default().push(video);
+    }
+    Ok(days)
+}
+
+/// Videos first inserted at or after `start` and before `end` (epoch millis), newest first.
+#[tauri::command]
+async fn get_videos_added_between(db: State<'_, Database>, start: i64, end: i64) -> Result<Vec<VideoMetadata>, AppError> {
+    database::query_videos(
+        &db.conn(),
+        "WHERE created_at >= ?1 AND created_at < ?2 ORDER BY created_at DESC",
+        [start, end],
+    )
+}
+
+/// Finds videos whose encoder or recording device contains `search` (case-insensitive), e.g.
+/// "obs", "handbrake" or "iphone 13".
+#[tauri::command]
+async fn get_videos_by_encoder(db: State<'_, Database>, search: String) -> Result<Vec<VideoMetadata>, AppError> {
+    database::query_videos(
+        &db.conn(),
+        "WHERE instr(lower(encoder), ?1) > 0 OR instr(lower(device_model), ?1) > 0
+         ORDER BY full_path",
+        [search.trim().to_lowercase()],
+    )
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FolderSummary {
+    pub folder_name: String,
+    pub video_count: u64,
+}
+
+/// The folders of the stored videos with their video counts, sorted by name. Folders only exist
+/// through their videos, so there are no empty ones.
+#[tauri::command]
+async fn get_folders(db: State<'_, Database>) -> Result<Vec<FolderSummary>, AppError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(
+        "SELECT folder_name, COUNT(*) FROM videos GROUP BY folder_name ORDER BY folder_name"
+    )?;
+    let folders = stmt.query_map([], |row| {
+        Ok(FolderSummary { folder_name: row.get(0)?, video_count: row.get(1)? })
+    })?;
+    Ok(folders.collect::<Result<Vec<_>, _>>()?)
+}
+
+#[tauri::command]
+async fn insert_video_record(db: State<'_, Database>, video: VideoMetadata) -> Result<(), AppError> {
+    Ok(database::upsert_video(&db.conn(), &video)?)
+}
+
+/// Removes every stored video, along with their tag links. Tags, settings and view states are
+/// kept; the files themselves are not touched.
+#[tauri::command]
+async fn clear_video_database(db: State<'_, Database>) -> Result<(), AppError> {
+    db.conn().execute("DELETE FROM videos", [])?;
+    Ok(())
+}
+
+/// Indexes the videos under `directory_path` into the database and returns them. Only new files
+/// and files whose size or modification time changed are probed; the rest come from their
+/// stored rows. Reports "crawl-progress" events like `crawl_directory`.
+#[tauri::command]
+async fn index_directory(app: AppHandle, directory_path: String, options: Option<CrawlOptions>) -> Result<IndexReport, AppError> {
+    index_changed_files(&app, &directory_path, &options.unwrap_or_default()).await
+}
+
+/// The modification time of a file as stored in `VideoMetadata::modified_date`.
+fn modified_timestamp(metadata: &fs::Metadata) -> String {
+    let modified = metadata.modified()
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    format_timestamp(modified)
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct IndexSummary {
+    /// Video files found.
+    pub scanned: u64,
+    /// New or changed files that were probed and stored.
+    pub reprobed: u64,
+    /// Files whose size and modification time match their stored row, so weren't probed.
+    pub skipped: u64,
+    /// Files that couldn't be probed; `IndexReport::errors` says why.
+    pub failed: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct IndexReport {
+    /// Every video found that is in the database, probed or not.
+    pub videos: Vec<VideoMetadata>,
+    pub summary: IndexSummary,
+    /// Files that couldn't be probed and directory entries that couldn't be listed.
+    pub errors: Vec<CrawlError>,
+}
+
+/// Indexes the videos under `path`, probing only files that are new or whose size or
+/// modification time differs from the stored row. Reports "crawl-progress" events like
+/// `crawl_directory`, plus one for the last file.
+async fn index_changed_files(app: &AppHandle, path: &str, options: &CrawlOptions) -> Result<IndexReport, AppError> {
+    let db = app.state::<Database>();
+    let (clause, values) = database::VideoFilter { path_prefix: Some(path.to_string()), ..Default::default() }.to_sql();
+    let mut stored: std::collections::HashMap<String, VideoMetadata> =
+        database::query_videos(&db.conn(), &clause, rusqlite::params_from_iter(values))?
+            .into_iter()
+            .map(|video| (video.full_path.clone(), video))
+            .collect();
+    
+    let _ = app.emit("crawl-progress", CrawlProgress {
+        phase: CrawlPhase::Scanning,
+        processed: 0,
+        total: None,
+        current_path: None,
+        eta_seconds: None,
+    });
+    let (root, walk_options) = (path.to_string(), options.clone());
+    let (candidates, mut errors) = tauri::async_runtime::spawn_blocking(move || {
+        let mut errors = Vec::new();
+        let mut candidates: Vec<std::path::PathBuf> = Vec::new();
+        for entry in crawl_walker(&root, &walk_options)? {
+            match entry {
+                Ok(entry) if walk_options.selects(&entry) => candidates.push(entry.into_path()),
+                Ok(_) => {}
+                Err(e) => errors.push(CrawlError {
+                    path: e.path().map(|p| p.to_string_lossy().to_string()).unwrap_or_else(|| root.clone()),
+                    message: e.to_string(),
+                }),
+            }
+        }
+        Ok::<_, AppError>((candidates, errors))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    let total = candidates.len() as u64;
+    
+    let mut throttle = power::BatteryThrottle::new(power::battery_policy(app));
+    let capabilities = playback::playback_capabilities(&db.conn());
+    let mut summary = IndexSummary { scanned: total, ..Default::default() };
+    let mut videos = Vec::with_capacity(candidates.len());
+    let extraction_started = std::time::Instant::now();
+    let mut pacer = ProgressPacer::new();
+    let mut probed: u64 = 0;
+    for (index, candidate) in candidates.iter().enumerate() {
+        let key = database::normalize_path(&candidate.to_string_lossy());
+        let unchanged = match (fs::metadata(candidate), stored.get(&key)) {
+            (Ok(metadata), Some(row)) => row.file_size == metadata.len() && row.modified_date == modified_timestamp(&metadata),
+            _ => false,
+        };
+        
+        if unchanged {
+            summary.skipped += 1;
+            videos.extend(stored.remove(&key));
+        } else {
+            let started = std::time::Instant::now();
+            match extract_for_crawl(candidate, options).await {
+                Ok(mut video) => {
+                    video.web_ready = playback::web_ready(&video, &capabilities);
+                    database::upsert_video(&db.conn(), &video)?;
+                    summary.reprobed += 1;
+                    videos.push(video);
+                }
+                Err(e) => {
+                    errors.push(CrawlError {
+                        path: candidate.to_string_lossy().to_string(),
+                        message: e.to_string(),
+                    });
+                    summary.failed += 1;
+                }
+            }
+            throttle.pace(started.elapsed(), || false).await;
+            probed += 1;
+        }
+        
+        // Unchanged files are nearly free, so the ETA assumes the remaining ones need probing
+        // at the rate measured so far.
+        let processed = index as u64 + 1;
+        if !pacer.due() && processed < total {
+            continue;
+        }
+        let eta_seconds = (probed > 0).then(|| {
+            extraction_started.elapsed().as_secs_f64() / probed as f64 * (total - processed) as f64
+        });
+        let _ = app.emit("crawl-progress", CrawlProgress {
+            phase: CrawlPhase::Extracting,
+            processed,
+            total: Some(total),
+            current_path: Some(candidate.to_string_lossy().to_string()),
+            eta_seconds,
+        });
+    }
+    
+    Ok(IndexReport { videos, summary, errors })
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AuditMismatch {
+    pub path: String,
+    /// Value in the database and on disk: sizes in bytes, or epoch-millis timestamps.
+    pub stored: String,
+    pub actual: String,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct LibraryAudit {
+    /// Stored videos under the root whose file no longer exists.
+    pub missing_from_disk: Vec<String>,
+    /// Video files under the root that aren't indexed.
+    pub missing_from_db: Vec<String>,
+    pub size_mismatches: Vec<AuditMismatch>,
+    /// Files of the stored size that were modified since being indexed.
+    pub mtime_mismatches: Vec<AuditMismatch>,
+}
+
+/// Compares the stored videos under `root` against a fresh walk of the directory without
+/// probing or changing anything. An empty report means an incremental re-index has nothing to do.
+#[tauri::command]
+async fn audit_library(db: State<'_, Database>, root: String) -> Result<LibraryAudit, AppError> {
+    let root_path = std::path::PathBuf::from(database::normalize_path(&root));
+    if !root_path.is_dir() {
+        return Err(AppError::InvalidInput(format!("{} is not a directory", root)));
+    }
+    
+    // Path::starts_with compares whole components, so /videos doesn't claim /videos2.
+    let mut stored: std::collections::BTreeMap<String, (u64, String)> = {
+        let conn = db.conn();
+        let mut stmt = conn.prepare("SELECT full_path, file_size, modified_date FROM videos")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, (row.get::<_, u64>(1)?, row.get::<_, i64>(2)?.to_string())))
+        })?;
+        rows.filter(|row| row.as_ref().map_or(true, |(path, _)| std::path::Path::new(path).starts_with(&root_path)))
+            .collect::<Result<_, _>>()?
+    };
+    
+    let mut audit = LibraryAudit::default();
+    for entry in WalkDir::new(&root_path).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() || !is_video_file(entry.path()) {
+            continue;
+        }
+        let path = database::normalize_path(&entry.path().to_string_lossy());
+        let Some((stored_size, stored_modified)) = stored.remove(&path) else {
+            audit.missing_from_db.push(path);
+            continue;
+        };
+        let Ok(metadata) = entry.metadata() else { continue };
+        
+        if metadata.len() != stored_size {
+            audit.size_mismatches.push(AuditMismatch {
+                path,
+                stored: stored_size.to_string(),
+                actual: metadata.len().to_string(),
+            });
+        } else if modified_timestamp(&metadata) != stored_modified {
+            audit.mtime_mismatches.push(AuditMismatch {
+                path,
+                stored: stored_modified,
+                actual: modified_timestamp(&metadata),
+            });
+        }
+    }
+    audit.missing_from_disk = stored.into_keys().collect();
+    audit.missing_from_db.sort();
+    
+    Ok(audit)
+}
+
+/// Moves the file at `from` to `to`, copying and deleting it when they are on different
+/// filesystems. A file already at `to` is replaced.
+pub(crate) fn move_file(from: &std::path::Path, to: &std::path::Path) -> std::io::Result<()> {
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            fs::copy(from, to)?;
+            // Never leave two copies behind.
+            if let Err(e) = fs::remove_file(from) {
+                let _ = fs::remove_file(to);
+                return Err(e);
+            }
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// Moves or renames a video file, across drives if needed, and updates its database row to
+/// match. Its cached thumbnails are renamed to the names they have for the new path. Fails
+/// rather than replacing a file at `new_path` unless `overwrite` is set, in which case that
+/// file's row is dropped. Recorded into the active operation, if any.
+#[tauri::command]
+async fn move_video(
+    db: State<'_, Database>,
+    active: State<'_, operations::ActiveOperation>,
+    video_id: String,
+    new_path: String,
+    overwrite: Option<bool>,
+) -> Result<VideoMetadata, AppError> {
+    let before = database::video_by_id(&db.conn(), &video_id)?;
+    let normalized_new_path = database::normalize_path(&new_path);
+    if normalized_new_path == before.full_path {
+        return Ok(before);
+    }
+    let source = std::path::Path::new(&before.full_path);
+    let destination = std::path::Path::new(&new_path);
+    if destination.exists() && !overwrite.unwrap_or(false) {
+        return Err(AppError::InvalidInput(format!("{} already exists", new_path)));
+    }
+    // Cached images are named after the path, size and mtime, which is gone after the move.
+    let thumbnail_prefix = thumbnail_path_for(source, "").ok()
+        .and_then(|prefix| Some(prefix.file_name()?.to_string_lossy().to_string()));
+    // A move across drives is a full copy, so it runs off the async workers and without
+    // holding the database.
+    let (from, to) = (source.to_path_buf(), destination.to_path_buf());
+    tauri::async_runtime::spawn_blocking(move || move_file(&from, &to))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let conn = db.conn();
+    let (folder_name, file_name) = path_names(destination);
+    let mut moved = before.clone();
+    moved.id = make_video_id(&folder_name, &file_name);
+    moved.folder_name = folder_name;
+    moved.file_name = file_name;
+    moved.full_path = normalized_new_path;
+    if let Some(prefix) = &thumbnail_prefix {
+        for thumbnail in [&mut moved.thumbnail_path, &mut moved.motion_thumbnail_path] {
+            let Some(old) = thumbnail.clone() else { continue };
+            let Some(suffix) = std::path::Path::new(&old).file_name()
+                .and_then(|name| name.to_string_lossy().strip_prefix(prefix.as_str()).map(str::to_string))
+            else { continue };
+            let Ok(renamed) = thumbnail_path_for(destination, &suffix) else { continue };
+            if fs::rename(&old, &renamed).is_ok() {
+                let renamed = database::normalize_path(&renamed.to_string_lossy());
+                conn.execute("UPDATE thumbnail_access SET path = ?1 WHERE path = ?2", [&renamed, &old])?;
+                *thumbnail = Some(renamed);
+            }
+        }
+    }
+
+    conn.execute("DELETE FROM videos WHERE full_path = ?1", [&moved.full_path])?;
+    // Updating full_path in place (rather than delete + insert) keeps tag links via ON UPDATE CASCADE.
+    conn.execute(
+        "UPDATE videos SET id = ?1, folder_name = ?2, file_name = ?3, full_path = ?4, thumbnail_path = ?5,
+            motion_thumbnail_path = ?6
+         WHERE full_path = ?7",
+        rusqlite::params![
+            moved.id, moved.folder_name, moved.file_name, moved.full_path, moved.thumbnail_path,
+            moved.motion_thumbnail_path, before.full_path,
+        ],
+    )?;
+
+    if let Some(operation_id) = active.current() {
+        // Undo restores `before`, which should point at the thumbnails where they are now.
+        let before = VideoMetadata {
+            thumbnail_path: moved.thumbnail_path.clone(),
+            motion_thumbnail_path: moved.motion_thumbnail_path.clone(),
+            ..before
+        };
+        operations::record_move(&conn, operation_id, &before, &moved.full_path)?;
+    }
+    Ok(moved)
+}
+
+/// Deletes a video's file, into the OS trash when `to_trash` is set and for good otherwise,
+/// then its row (with its tag links) and its thumbnails. If the file can't be removed the row
+/// is kept and the error returned.
+#[tauri::command]
+async fn delete_video(db: State<'_, Database>, video_id: String, to_trash: bool) -> Result<(), AppError> {
+    let video = database::video_by_id(&db.conn(), &video_id)?;
+    let path = std::path::Path::new(&video.full_path);
+    if to_trash {
+        // Also covers drives other than the one with the home directory, via their own trash folder.
+        trash::delete(path).map_err(|e| AppError::Io(format!("Moving {} to the trash failed: {}", path.display(), e)))?;
+    } else {
+        fs::remove_file(path)?;
+    }
+    
+    let conn = db.conn();
+    conn.execute("DELETE FROM videos WHERE full_path = ?1", [&video.full_path])?;
+    for thumbnail in [&video.thumbnail_path, &video.motion_thumbnail_path].into_iter().flatten() {
+        let _ = fs::remove_file(thumbnail);
+        conn.execute("DELETE FROM thumbnail_access WHERE path = ?1", [thumbnail])?;
+    }
+    Ok(())
+}
+
+/// How the user last arranged a folder's grid. The field values are defined by the frontend;
+/// only `sort_direction` is checked here.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ViewState {
+    pub sort_field: String,
+    /// Either "asc" or "desc".
+    pub sort_direction: String,
+    pub view_mode: String,
+}
+
+#[tauri::command]
+async fn get_view_state(db: State<'_, Database>, folder_name: String) -> Result<Option<ViewState>, AppError> {
+    use rusqlite::OptionalExtension;
+    
+    Ok(db.conn().query_row(
+        "SELECT sort_field, sort_direction, view_mode FROM view_state WHERE folder_name = ?1",
+        [&folder_name],
+        |row| Ok(ViewState {
+            sort_field: row.get(0)?,
+            sort_direction: row.get(1)?,
+            view_mode: row.get(2)?,
+        }),
+    ).optional()?)
+}
+
+#[tauri::command]
+async fn set_view_state(db: State<'_, Database>, folder_name: String, state: ViewState) -> Result<(), AppError> {
+    if state.sort_direction != "asc" && state.sort_direction != "desc" {
+        return Err(AppError::InvalidInput(format!("Invalid sort direction '{}', expected 'asc' or 'desc'", state.sort_direction)));
+    }
+    
+    db.conn().execute(
+        "INSERT INTO view_state (folder_name, sort_field, sort_direction, view_mode)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(folder_name) DO UPDATE SET
+            sort_field = excluded.sort_field,
+            sort_direction = excluded.sort_direction,
+            view_mode = excluded.view_mode",
+        rusqlite::params![folder_name, state.sort_field, state.sort_direction, state.view_mode],
+    )?;
+    Ok(())
+}
+
+/// SplitMix64: a tiny, well-distributed PRNG. Used where the same seed must always give the same
+/// sequence across runs and platforms, which `rand`'s generators don't promise.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Returns the folder's videos in a pseudo-random order that only depends on `seed` and the
+/// folder's contents, so a "sticky" shuffle survives restarts as long as the seed is kept.
+#[tauri::command]
+async fn get_shuffled_order(db: State<'_, Database>, folder_name: String, seed: u64) -> Result<Vec<VideoMetadata>, AppError> {
+    // Start from a stable order so the shuffle doesn't depend on row storage order.
+    let mut videos = database::query_videos(
+        &db.conn(),
+        "WHERE folder_name = ?1 ORDER BY full_path",
+        [&folder_name],
+    )?;
+    
+    // Fisher-Yates shuffle
+    let mut rng = SplitMix64(seed);
+    for i in (1..videos.len()).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        videos.swap(i, j);
+    }
+    
+    Ok(videos)
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct OversizedVideo {
+    pub video: VideoMetadata,
+    pub display_width: u32,
+    pub display_height: u32,
+    /// Display size after scaling down to the requested height, keeping the aspect ratio.
+    pub target_width: u32,
+    pub target_height: u32,
+    /// Rough size after downscaling, assuming the bitrate scales with the pixel count.
+    pub estimated_size: u64,
+    pub estimated_savings: u64,
+}
+
+/// Lists videos whose displayed height (see `VideoMetadata::display_height`) exceeds
+/// `max_height`, tallest first, with an estimate of what downscaling them would save.
+#[tauri::command]
+async fn find_videos_above_resolution(db: State<'_, Database>, max_height: u32) -> Result<Vec<OversizedVideo>, AppError> {
+    if max_height == 0 {
+        return Err(AppError::InvalidInput("max_height must be greater than zero".to_string()));
+    }
+    
+    // Rows indexed before display dimensions were stored fall back to the coded size.
+    let videos = database::query_videos(
+        &db.conn(),
+        "WHERE COALESCE(display_height, height) > ?1
+         ORDER BY COALESCE(display_height, height) DESC, full_path",
+        [max_height],
+    )?;
+    
+    Ok(videos.into_iter()
+        .filter_map(|video| {
+            let display_width = video.display_width.or(video.width)?;
+            let display_height = video.display_height.or(video.height)?;
+            let scale = max_height as f64 / display_height as f64;
+            // Encoders want even dimensions.
+            let target_width = ((display_width as f64 * scale / 2.0).round() as u32 * 2).max(2);
+            let estimated_size = (video.file_size as f64 * scale * scale).round() as u64;
+            Some(OversizedVideo {
+                display_width,
+                display_height,
+                target_width,
+                target_height: max_height,
+                estimated_size,
+                estimated_savings: video.file_size.saturating_sub(estimated_size),
+                video,
+            })
+        })
+        .collect())
+}
+
+/// Named aspect ratios that display sizes are snapped to, as `(label, width / height)`.
+const COMMON_ASPECT_RATIOS: &[(&str, f64)] = &[
+    ("21:9", 64.0 / 27.0),
+    ("2:1", 2.0),
+    ("16:9", 16.0 / 9.0),
+    ("16:10", 1.6),
+    ("3:2", 1.5),
+    ("4:3", 4.0 / 3.0),
+    ("5:4", 1.25),
+    ("1:1", 1.0),
+    ("4:5", 0.8),
+    ("3:4", 0.75),
+    ("9:16", 9.0 / 16.0),
+];
+
+/// How far (relative) a ratio may be off a named one and still count as it, so 1920x1088 or
+/// 854x480 are 16:9.
+const ASPECT_RATIO_TOLERANCE: f64 = 0.02;
+
+/// Classifies a display size as the closest named aspect ratio, or as its reduced `w:h` if none is
+/// close. Returns the label and the ratio it stands for.
+fn aspect_bucket(width: u32, height: u32) -> (String, f64) {
+    let ratio = width as f64 / height as f64;
+    let closest = COMMON_ASPECT_RATIOS.iter()
+        .min_by(|a, b| (a.1 - ratio).abs().total_cmp(&(b.1 - ratio).abs()))
+        .filter(|(_, common)| (common - ratio).abs() / common <= ASPECT_RATIO_TOLERANCE);
+    match closest {
+        Some((label, common)) => (label.to_string(), *common),
+        None => {
+            let gcd = |mut a: u32, mut b: u32| {
+                while b != 0 {
+                    (a, b) = (b, a % b);
+                }
+                a
+            };
+            let divisor = gcd(width, height).max(1);
+            (format!("{}:{}", width / divisor, height / divisor), ratio)
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AspectRatioShare {
+    /// E.g. "16:9", or the reduced size for uncommon shapes.
+    pub label: String,
+    /// Width divided by height.
+    pub ratio: f64,
+    pub video_count: u64,
+    /// Fraction of the counted videos (0-1) with this aspect ratio.
+    pub share: f64,
+}
+
+/// Most common display aspect ratio among the stored videos of `folder_name`, or of the whole
+/// library without one. `None` when no video has known dimensions.
+fn dominant_aspect_ratio(conn: &rusqlite::Connection, folder_name: Option<&str>) -> Result<Option<AspectRatioShare>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT COALESCE(display_width, width), COALESCE(display_height, height) FROM videos
+         WHERE COALESCE(display_width, width) > 0 AND COALESCE(display_height, height) > 0
+           AND (?1 IS NULL OR folder_name = ?1)"
+    )?;
+    let sizes = stmt.query_map([folder_name], |row| Ok((row.get::<_, u32>(0)?, row.get::<_, u32>(1)?)))?;
+    
+    let mut counts: std::collections::HashMap<String, (f64, u64)> = std::collections::HashMap::new();
+    let mut total = 0;
+    for size in sizes {
+        let (width, height) = size?;
+        let (label, ratio) = aspect_bucket(width, height);
+        counts.entry(label).or_insert((ratio, 0)).1 += 1;
+        total += 1;
+    }
+    
+    // Ties go to the wider shape so the result doesn't depend on hash order.
+    Ok(counts.into_iter()
+        .max_by(|(_, (ratio_a, count_a)), (_, (ratio_b, count_b))| count_a.cmp(count_b).then(ratio_a.total_cmp(ratio_b)))
+        .map(|(label, (ratio, video_count))| AspectRatioShare {
+            label,
+            ratio,
+            video_count,
+            share: video_count as f64 / total as f64,
+        }))
+}
+
+/// Most common display aspect ratio across the library, for sizing grid tiles.
+#[tauri::command]
+async fn get_dominant_aspect_ratio(db: State<'_, Database>) -> Result<Option<AspectRatioShare>, AppError> {
+    dominant_aspect_ratio(&db.conn(), None)
+}
+
+/// Most common display aspect ratio among the videos of one folder.
+#[tauri::command]
+async fn get_folder_dominant_aspect_ratio(db: State<'_, Database>, folder_name: String) -> Result<Option<AspectRatioShare>, AppError> {
+    dominant_aspect_ratio(&db.conn(), Some(&folder_name))
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AspectOutlier {
+    pub video: VideoMetadata,
+    /// Bucketed like `AspectRatioShare::label`.
+    pub label: String,
+    pub ratio: f64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AspectOutlierReport {
+    /// `None` when no video of the folder has known dimensions.
+    pub dominant: Option<AspectRatioShare>,
+    pub outliers: Vec<AspectOutlier>,
+}
+
+/// Lists the videos of a folder whose display aspect ratio differs from the folder's dominant
+/// one, e.g. a 4:3 episode in a 16:9 series. Uses the stored dimensions only; videos without
+/// known dimensions are skipped.
+#[tauri::command]
+async fn find_aspect_outliers(db: State<'_, Database>, folder_name: String) -> Result<AspectOutlierReport, AppError> {
+    let conn = db.conn();
+    let Some(dominant) = dominant_aspect_ratio(&conn, Some(&folder_name))? else {
+        return Ok(AspectOutlierReport { dominant: None, outliers: Vec::new() });
+    };
+    
+    let videos = database::query_videos(&conn, "WHERE folder_name = ?1 ORDER BY file_name", [&folder_name])?;
+    let outliers = videos.into_iter()
+        .filter_map(|video| {
+            let width = video.display_width.or(video.width).filter(|w| *w > 0)?;
+            let height = video.display_height.or(video.height).filter(|h| *h > 0)?;
+            let (label, ratio) = aspect_bucket(width, height);
+            (label != dominant.label).then_some(AspectOutlier { video, label, ratio })
+        })
+        .collect();
+    
+    Ok(AspectOutlierReport { dominant: Some(dominant), outliers })
+}
+
+/// Recomputes `decode_difficulty` for every stored video from its stored metadata (no probing),
+/// e.g. for rows indexed before the score existed. Returns the number of rows updated.
+#[tauri::command]
+async fn recompute_decode_difficulty(db: State<'_, Database>) -> Result<u64, AppError> {
+    let mut conn = db.conn();
+    let videos = database::query_videos(&conn, "", [])?;
+    
+    let tx = conn.transaction()?;
+    for video in &videos {
+        tx.execute(
+            "UPDATE videos SET decode_difficulty = ?1 WHERE full_path = ?2",
+            rusqlite::params![decode_difficulty(video), video.full_path],
+        )?;
+    }
+    tx.commit()?;
+    
+    Ok(videos.len() as u64)
+}
+
+/// Payload of the "reprobe-progress" event.
+#[derive(Debug, Serialize, Clone)]
+pub struct ReprobeProgress {
+    pub processed: u64,
+    pub total: u64,
+    pub current_path: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ReprobeFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ReprobeReport {
+    pub updated: u64,
+    pub failed: Vec<ReprobeFailure>,
+}
+
+/// Re-extracts the metadata of every stored video matching `filter` and updates the rows,
+/// reporting "reprobe-progress" events to the calling window. Thumbnails and results of
+/// on-demand probes are carried over, everything ffprobe reports is replaced.
+#[tauri::command]
+async fn reprobe_videos(app: AppHandle, window: tauri::Window, db: State<'_, Database>, filter: database::VideoFilter) -> Result<ReprobeReport, AppError> {
+    let (clause, values) = filter.to_sql();
+    let videos = database::query_videos(&db.conn(), &clause, rusqlite::params_from_iter(values))?;
+    let total = videos.len() as u64;
+    let mut throttle = power::BatteryThrottle::new(power::battery_policy(&app));
+    let capabilities = playback::playback_capabilities(&db.conn());
+    
+    let mut report = ReprobeReport { updated: 0, failed: Vec::new() };
+    for (index, stored) in videos.into_iter().enumerate() {
+        let started = std::time::Instant::now();
+        let _ = window.emit("reprobe-progress", ReprobeProgress {
+            processed: index as u64,
+            total,
+            current_path: stored.full_path.clone(),
+        });
+        
+        match extract_video_metadata(std::path::Path::new(&stored.full_path), DEFAULT_PROBE_TIMEOUT).await {
+            Ok(mut video) => {
+                video.thumbnail_path = stored.thumbnail_path;
+                video.motion_thumbnail_path = stored.motion_thumbnail_path;
+                video.starts_with_keyframe = stored.starts_with_keyframe;
+                video.motion_type = stored.motion_type;
+                video.content_hash = stored.content_hash;
+                video.playable = stored.playable;
+                video.web_ready = playback::web_ready(&video, &capabilities);
+                database::upsert_video(&db.conn(), &video)?;
+                report.updated += 1;
+            }
+            Err(e) => report.failed.push(ReprobeFailure { path: stored.full_path, error: e.to_string() }),
+        }
+        throttle.pace(started.elapsed(), || false).await;
+    }
+    
+    Ok(report)
+}
+
+/// Re-extracts metadata for the stored videos of one codec, e.g. after fixing how that codec is
+/// probed. Shorthand for `reprobe_videos` with only `codec` set.
+#[tauri::command]
+async fn reprobe_by_codec(app: AppHandle, window: tauri::Window, db: State<'_, Database>, codec: String) -> Result<ReprobeReport, AppError> {
+    let filter = database::VideoFilter { codec: Some(codec), ..Default::default() };
+    reprobe_videos(app, window, db, filter).await
+}
+
+/// Extracts the metadata of a single file, e.g. one just written by a transcode, without
+/// crawling its directory. The library is left unchanged; re-index the file to store the result.
+#[tauri::command]
+async fn probe_video(db: State<'_, Database>, path: String) -> Result<VideoMetadata, AppError> {
+    let mut video = extract_video_metadata(std::path::Path::new(&path), DEFAULT_PROBE_TIMEOUT).await?;
+    video.web_ready = playback::web_ready(&video, &playback::playback_capabilities(&db.conn()));
+    Ok(video)
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PathRepairReport {
+    /// Rows whose video or thumbnail path was respelled.
+    pub rewritten: u64,
+    /// Rows dropped because another row already had the normalized path. Their tags were
+    /// moved to that row first.
+    pub merged: u64,
+}
+
+/// Rewrites every stored video and thumbnail path into the form `database::normalize_path`
+/// produces, merging rows that turn out to be the same file under two spellings.
+#[tauri::command]
+async fn normalize_stored_paths(db: State<'_, Database>) -> Result<PathRepairReport, AppError> {
+    use rusqlite::OptionalExtension;
+    
+    let mut conn = db.conn();
+    let tx = conn.transaction()?;
+    let rows: Vec<(String, Option<String>)> = {
+        let mut stmt = tx.prepare("SELECT full_path, thumbnail_path FROM videos")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<_, _>>()?
+    };
+    
+    let mut report = PathRepairReport { rewritten: 0, merged: 0 };
+    for (full_path, thumbnail_path) in rows {
+        let normalized = database::normalize_path(&full_path);
+        if normalized != full_path {
+            let duplicate = tx.query_row("SELECT 1 FROM videos WHERE full_path = ?1", [&normalized], |_| Ok(()))
+                .optional()?;
+            if duplicate.is_some() {
+                tx.execute(
+                    "INSERT OR IGNORE INTO video_tags (video_path, tag_id)
+                     SELECT ?1, tag_id FROM video_tags WHERE video_path = ?2",
+                    [&normalized, &full_path],
+                )?;
+                tx.execute("DELETE FROM videos WHERE full_path = ?1", [&full_path])?;
+                report.merged += 1;
+                continue;
+            }
+            tx.execute("UPDATE videos SET full_path = ?1 WHERE full_path = ?2", [&normalized, &full_path])?;
+        }
+        
+        let normalized_thumbnail = thumbnail_path.as_deref().map(database::normalize_path);
+        if normalized_thumbnail != thumbnail_path {
+            tx.execute(
+                "UPDATE videos SET thumbnail_path = ?1 WHERE full_path = ?2",
+                rusqlite::params![normalized_thumbnail, normalized],
+            )?;
+        }
+        if normalized != full_path || normalized_thumbnail != thumbnail_path {
+            report.rewritten += 1;
+        }
+    }
+    tx.commit()?;
+    
+    Ok(report)
+}
+
+/// Default share by which a file may fall short of its expected size before it is suspect.
+const DEFAULT_TRUNCATION_TOLERANCE: f64 = 0.1;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TruncationSuspect {
+    pub video: VideoMetadata,
+    /// Current size on disk (0 if the file is gone).
+    pub actual_size: u64,
+    /// Size implied by the stored bitrate and duration, or the stored size if that is larger.
+    pub expected_size: u64,
+    /// `actual_size / expected_size`.
+    pub ratio: f64,
+}
+
+/// Flags videos of `folder_name` that are much smaller than their stored bitrate and duration
+/// imply, or than when they were indexed: likely truncated downloads or copies. This only
+/// compares sizes, so use it to pick candidates for a full decode check.
+///
+/// The stream bitrate is preferred over the overall one, since for many containers ffprobe
+/// derives the overall bitrate from the file size itself. `tolerance` (default 0.1) is the share
+/// by which a file may fall short of the expected size.
 #[tauri::command]
-async fn clear_video_database() -> Result<(), String> {
-    // Clear video database - placeholder implementation
-    Ok(())
+async fn quick_integrity_check(db: State<'_, Database>, folder_name: String, tolerance: Option<f64>) -> Result<Vec<TruncationSuspect>, AppError> {
+    let tolerance = tolerance.unwrap_or(DEFAULT_TRUNCATION_TOLERANCE).clamp(0.0, 1.0);
+    let videos = database::query_videos(
+        &db.conn(),
+        "WHERE folder_name = ?1 ORDER BY file_name",
+        [&folder_name],
+    )?;
+    
+    Ok(videos.into_iter()
+        .filter_map(|video| {
+            let actual_size = fs::metadata(&video.full_path).map(|m| m.len()).unwrap_or(0);
+            let from_bitrate = match (video.video_bitrate.or(video.total_bitrate), video.duration) {
+                (Some(bitrate), Some(duration)) => (bitrate as f64 * duration / 8.0) as u64,
+                _ => 0,
+            };
+            let expected_size = from_bitrate.max(video.file_size);
+            if expected_size == 0 || actual_size as f64 >= expected_size as f64 * (1.0 - tolerance) {
+                return None;
+            }
+            Some(TruncationSuspect {
+                ratio: actual_size as f64 / expected_size as f64,
+                actual_size,
+                expected_size,
+                video,
+            })
+        })
+        .collect())
+}
+
+/// Folders whose average bitrate falls below this are flagged as low quality (2 Mbps).
+const DEFAULT_LOW_BITRATE_THRESHOLD: u64 = 2_000_000;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FolderBitrateStats {
+    pub folder_name: String,
+    /// Number of videos in the folder with a known bitrate.
+    pub video_count: u64,
+    pub average_bitrate: f64,
+    pub min_bitrate: u64,
+    pub max_bitrate: u64,
+    pub low_quality: bool,
+}
+
+/// Per-folder bitrate aggregates for spotting folders of low-quality footage.
+/// Videos without a stored bitrate are ignored.
+#[tauri::command]
+async fn get_folder_bitrate_stats(db: State<'_, Database>, low_bitrate_threshold: Option<u64>) -> Result<Vec<FolderBitrateStats>, AppError> {
+    let threshold = low_bitrate_threshold.unwrap_or(DEFAULT_LOW_BITRATE_THRESHOLD) as f64;
+    let conn = db.conn();
+    let mut stmt = conn.prepare(
+        "SELECT folder_name, COUNT(total_bitrate), AVG(total_bitrate), MIN(total_bitrate), MAX(total_bitrate)
+         FROM videos
+         WHERE total_bitrate IS NOT NULL
+         GROUP BY folder_name
+         ORDER BY folder_name"
+    )?;
+    
+    let stats = stmt.query_map([], |row| {
+        let average_bitrate: f64 = row.get(2)?;
+        Ok(FolderBitrateStats {
+            folder_name: row.get(0)?,
+            video_count: row.get(1)?,
+            average_bitrate,
+            min_bitrate: row.get(3)?,
+            max_bitrate: row.get(4)?,
+            low_quality: average_bitrate < threshold,
+        })
+    })?;
+    
+    Ok(stats.collect::<Result<Vec<_>, _>>()?)
 }
 
+#[derive(Debug, Serialize, Clone)]
+pub struct LibraryStats {
+    pub total_videos: u64,
+    /// Bytes.
+    pub total_size: u64,
+    /// Seconds, over the videos with a known duration.
+    pub total_duration: f64,
+    /// Average `total_bitrate` of the videos that have one.
+    pub average_bitrate: Option<f64>,
+    /// Number of videos per codec and per `ResolutionClass` name; "unknown" when not stored.
+    pub by_codec: std::collections::BTreeMap<String, u64>,
+    pub by_resolution_class: std::collections::BTreeMap<String, u64>,
+    pub largest_video: Option<VideoMetadata>,
+    pub longest_video: Option<VideoMetadata>,
+}
+
+/// Headline numbers for the whole library, aggregated in SQL.
 #[tauri::command]
-async fn index_directory(directory_path: String) -> Result<Vec<VideoMetadata>, String> {
-    // Index directory - use existing crawl_directory implementation
-    crawl_directory(directory_path).await
+async fn get_library_stats(db: State<'_, Database>) -> Result<LibraryStats, AppError> {
+    let conn = db.conn();
+    let (total_videos, total_size, total_duration, average_bitrate) = conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(file_size), 0), COALESCE(SUM(duration), 0), AVG(total_bitrate) FROM videos",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    )?;
+    
+    let counts = |column: &str| -> Result<std::collections::BTreeMap<String, u64>, AppError> {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT COALESCE({}, 'unknown'), COUNT(*) FROM videos GROUP BY 1", column,
+        ))?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    };
+    let by_codec = counts("LOWER(codec)")?;
+    let by_resolution_class = counts("resolution_class")?;
+    
+    let largest_video = database::query_videos(&conn, "ORDER BY file_size DESC LIMIT 1", [])?.pop();
+    let longest_video = database::query_videos(
+        &conn,
+        "WHERE duration IS NOT NULL ORDER BY duration DESC LIMIT 1",
+        [],
+    )?.pop();
+    
+    Ok(LibraryStats {
+        total_videos,
+        total_size,
+        total_duration,
+        average_bitrate,
+        by_codec,
+        by_resolution_class,
+        largest_video,
+        longest_video,
+    })
 }
 
 #[tauri::command]
-async fn read_network_file(path: String) -> Result<Vec<u8>, String> {
+async fn read_network_file(path: String) -> Result<Vec<u8>, AppError> {
     use std::fs;
     
-    match fs::read(&path) {
-        Ok(data) => Ok(data),
-        Err(e) => Err(format!("Failed to read file: {}", e)),
-    }
+    Ok(fs::read(&path)?)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -350,30 +3122,48 @@ pub struct StreamChunk {
     pub is_complete: bool,
 }
 
+/// Like `video_stream::serve`, the byte-reading commands only read videos in the library, so the
+/// webview can't use them to read arbitrary files.
+fn check_indexed(app: &AppHandle, path: &str) -> Result<(), AppError> {
+    if !video_stream::is_indexed(app, path) {
+        return Err(AppError::NotFound(format!("Not an indexed video: {}", path)));
+    }
+    Ok(())
+}
+
+/// Reads `chunk_size` bytes of the indexed video `path` from `offset`. For playback prefer the
+/// `stream` URI scheme (see `video_stream`), which lets `<video>` request ranges itself.
 #[tauri::command]
-async fn stream_network_file_chunk(path: String, offset: u64, chunk_size: u64) -> Result<StreamChunk, String> {
+async fn stream_network_file_chunk(app: AppHandle, path: String, offset: u64, chunk_size: u64) -> Result<StreamChunk, AppError> {
+    check_indexed(&app, &path)?;
+    read_chunk(&path, offset, chunk_size).await
+}
+
+async fn read_chunk(path: &str, offset: u64, chunk_size: u64) -> Result<StreamChunk, AppError> {
     use std::fs::File;
     use std::io::{Read, Seek, SeekFrom};
     
-    let mut file = File::open(&path)
-        .map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut file = File::open(path)?;
     
     // Get file size
-    let metadata = file.metadata()
-        .map_err(|e| format!("Failed to get metadata: {}", e))?;
+    let metadata = file.metadata()?;
     let total_size = metadata.len();
     
+    // The offset can be past the end when the file shrank since the last chunk, e.g. a
+    // recording still being written on a share.
+    if offset >= total_size {
+        return Ok(StreamChunk { data: Vec::new(), offset, total_size, is_complete: true });
+    }
+    
     // Seek to offset
-    file.seek(SeekFrom::Start(offset))
-        .map_err(|e| format!("Failed to seek: {}", e))?;
+    file.seek(SeekFrom::Start(offset))?;
     
     // Calculate actual chunk size
     let actual_chunk_size = std::cmp::min(chunk_size, total_size - offset);
     let mut buffer = vec![0u8; actual_chunk_size as usize];
     
     // Read chunk
-    let bytes_read = file.read(&mut buffer)
-        .map_err(|e| format!("Failed to read: {}", e))?;
+    let bytes_read = file.read(&mut buffer)?;
     
     // Truncate to actual bytes read
     buffer.truncate(bytes_read);
@@ -386,40 +3176,72 @@ async fn stream_network_file_chunk(path: String, offset: u64, chunk_size: u64) -
     })
 }
 
-// Keep the original function for backward compatibility but make it faster
+#[derive(Debug, Serialize, Clone)]
+pub struct RangeResponse {
+    pub data: Vec<u8>,
+    /// First and last byte returned, inclusive like an HTTP `Content-Range`.
+    pub start: u64,
+    pub end: u64,
+    pub total_size: u64,
+}
+
+/// Reads bytes `start` to `end` (inclusive) of the indexed video `path`, for players that request
+/// HTTP-style ranges. `end` is clamped to the last byte of the file and to at most
+/// `video_stream::MAX_RESPONSE_BYTES` after `start`; the returned `end` is the last byte
+/// actually read. A `start` past the end or after `end` is an error.
+#[tauri::command]
+async fn read_file_range(app: AppHandle, path: String, start: u64, end: u64) -> Result<RangeResponse, AppError> {
+    check_indexed(&app, &path)?;
+    read_range(&path, start, end).await
+}
+
+async fn read_range(path: &str, start: u64, end: u64) -> Result<RangeResponse, AppError> {
+    use std::fs::File;
+    use std::io::{Read, Seek, SeekFrom};
+    
+    let mut file = File::open(path)?;
+    let total_size = file.metadata()?.len();
+    if start > end {
+        return Err(AppError::InvalidInput(format!("Range start {} is after its end {}", start, end)));
+    }
+    if start >= total_size {
+        return Err(AppError::InvalidInput(format!("Range start {} is past the end of the {}-byte file", start, total_size)));
+    }
+    let end = end.min(total_size - 1).min(start + video_stream::MAX_RESPONSE_BYTES - 1);
+    
+    let mut data = vec![0u8; (end - start + 1) as usize];
+    file.seek(SeekFrom::Start(start))?;
+    file.read_exact(&mut data)?;
+    Ok(RangeResponse { data, start, end, total_size })
+}
+
+/// Reads the whole file into memory, which is unusable for large files: use
+/// `stream_network_file_chunk` or the `stream` URI scheme instead.
 #[tauri::command]
-async fn stream_network_file(path: String) -> Result<Vec<u8>, String> {
+async fn stream_network_file(path: String) -> Result<Vec<u8>, AppError> {
     use std::fs::File;
     use std::io::{Read, Seek, SeekFrom};
     
-    println!("🚀 Starting to stream file: {}", path);
+    log::info!("Starting to stream file: {}", path);
     
     let mut file = File::open(&path)
-        .map_err(|e| {
-            let error_msg = format!("Failed to open file: {}", e);
-            println!("❌ {}", error_msg);
-            error_msg
-        })?;
+        .inspect_err(|e| log::error!("Failed to open file: {}", e))?;
     
-    println!("✅ File opened successfully");
+    log::debug!("File opened successfully");
     
     // Get file size first
     let metadata = file.metadata()
-        .map_err(|e| {
-            let error_msg = format!("Failed to get metadata: {}", e);
-            println!("❌ {}", error_msg);
-            error_msg
-        })?;
+        .inspect_err(|e| log::error!("Failed to get metadata: {}", e))?;
     let file_size = metadata.len();
     
-    println!("📊 File size: {} bytes ({:.2} MB)", file_size, file_size as f64 / (1024.0 * 1024.0));
+    log::debug!("File size: {} bytes ({:.2} MB)", file_size, file_size as f64 / (1024.0 * 1024.0));
     
     // For network drives, use smaller chunks and add more delays
     let chunk_size = if path.starts_with("\\\\") { 
-        println!("🌐 Network drive detected, using 64KB chunks");
+        log::debug!("Network drive detected, using 64KB chunks");
         64 * 1024 
     } else { 
-        println!("💾 Local drive detected, using 1MB chunks");
+        log::debug!("Local drive detected, using 1MB chunks");
         1024 * 1024 
     };
     
@@ -427,13 +3249,13 @@ async fn stream_network_file(path: String) -> Result<Vec<u8>, String> {
     let mut offset = 0;
     let mut chunk_count = 0;
     
-    println!("🔄 Starting to read file in chunks...");
+    log::debug!("Starting to read file in chunks...");
     
     while offset < file_size {
         chunk_count += 1;
         
         if chunk_count % 10 == 0 || chunk_count == 1 {
-            println!("📖 Reading chunk {} at offset {} ({}% complete)", 
+            log::debug!("Reading chunk {} at offset {} ({}% complete)", 
                 chunk_count, 
                 offset, 
                 (offset as f64 / file_size as f64 * 100.0) as u32
@@ -442,11 +3264,7 @@ async fn stream_network_file(path: String) -> Result<Vec<u8>, String> {
         
         // Seek to current offset
         file.seek(SeekFrom::Start(offset))
-            .map_err(|e| {
-                let error_msg = format!("Failed to seek to offset {}: {}", offset, e);
-                println!("❌ {}", error_msg);
-                error_msg
-            })?;
+            .inspect_err(|e| log::error!("Failed to seek to offset {}: {}", offset, e))?;
         
         // Calculate chunk size for this iteration
         let current_chunk_size = std::cmp::min(chunk_size, file_size - offset);
@@ -454,14 +3272,10 @@ async fn stream_network_file(path: String) -> Result<Vec<u8>, String> {
         
         // Read chunk
         let bytes_read = file.read(&mut chunk)
-            .map_err(|e| {
-                let error_msg = format!("Failed to read chunk at offset {}: {}", offset, e);
-                println!("❌ {}", error_msg);
-                error_msg
-            })?;
+            .inspect_err(|e| log::error!("Failed to read chunk at offset {}: {}", offset, e))?;
         
         if bytes_read == 0 {
-            println!("📄 End of file reached at offset {}", offset);
+            log::debug!("End of file reached at offset {}", offset);
             break; // End of file
         }
         
@@ -477,62 +3291,911 @@ async fn stream_network_file(path: String) -> Result<Vec<u8>, String> {
         }
     }
     
-    println!("✅ File streaming complete! Read {} chunks, {} bytes total", chunk_count, buffer.len());
+    log::info!("File streaming complete, read {} chunks, {} bytes total", chunk_count, buffer.len());
     Ok(buffer)
 }
 
+/// Number of trailing ffmpeg/ffprobe stderr lines included in error messages.
+const FFMPEG_ERROR_TAIL_LINES: usize = 20;
+
+/// Payload of the `ffmpeg-log` event, emitted for every stderr line of a long-running ffmpeg job
+/// except its progress status lines.
+#[derive(Debug, Serialize, Clone)]
+pub struct FfmpegLogLine {
+    pub input_path: String,
+    pub line: String,
+}
+
+/// Returns the last `max_lines` non-empty lines of a process' stderr output.
+fn stderr_tail(stderr: &str, max_lines: usize) -> String {
+    let lines: Vec<&str> = stderr.lines()
+        .map(|l| l.trim_end())
+        .filter(|l| !l.is_empty())
+        .collect();
+    lines[lines.len().saturating_sub(max_lines)..].join("\n")
+}
+
+/// Builds an error message for a failed ffmpeg/ffprobe run, appending the tail of its stderr
+/// so the UI can show which codec or option was rejected.
+fn ffmpeg_failure(context: &str, stderr: &str) -> String {
+    let tail = stderr_tail(stderr, FFMPEG_ERROR_TAIL_LINES);
+    if tail.is_empty() {
+        context.to_string()
+    } else {
+        format!("{}:\n{}", context, tail)
+    }
+}
+
+/// Encoder and filter choices for a web transcode. Missing fields fall back to the defaults
+/// (H.264 + AAC at CRF 23 with the "fast" preset, no filters, faststart).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct TranscodeOptions {
+    pub video_codec: String,
+    pub audio_codec: String,
+    /// Optional ffmpeg `-vf` filtergraph, e.g. `scale=-2:720,fps=30`.
+    pub video_filter: Option<String>,
+    /// When set, encode in two passes to land at about this many bytes instead of using CRF.
+    pub target_size: Option<u64>,
+    /// Audio bitrate in bits per second for target-size encodes.
+    /// Defaults to `DEFAULT_TARGET_AUDIO_BITRATE`.
+    pub audio_bitrate: Option<u64>,
+    /// Constant quality, 0 (lossless) to `MAX_CRF`; lower is better. Ignored for target-size encodes.
+    pub crf: u8,
+    /// Encoder speed/size tradeoff, one of `ENCODER_PRESETS`.
+    pub preset: String,
+    /// Scale down to this height (keeping the aspect ratio) when the source is taller.
+    pub max_height: Option<u32>,
+    /// Move the index to the front of the file so playback can start before it is fully loaded.
+    pub faststart: bool,
+}
+
+impl Default for TranscodeOptions {
+    fn default() -> Self {
+        TranscodeOptions {
+            video_codec: "libx264".to_string(), // H.264 for broad compatibility
+            audio_codec: "aac".to_string(),     // AAC audio
+            video_filter: None,
+            target_size: None,
+            audio_bitrate: None,
+            crf: 23,                            // Good quality
+            preset: "fast".to_string(),         // Fast encoding
+            max_height: None,
+            faststart: true,                    // Web optimization
+        }
+    }
+}
+
+/// Highest CRF accepted by the x264/x265 encoders.
+const MAX_CRF: u8 = 51;
+
+/// Presets understood by the x264/x265 encoders, fastest first.
+const ENCODER_PRESETS: &[&str] = &[
+    "ultrafast", "superfast", "veryfast", "faster", "fast", "medium", "slow", "slower", "veryslow", "placebo",
+];
+
+/// Rejects CRF, preset and height values ffmpeg would fail on with a less helpful message.
+fn check_transcode_settings(options: &TranscodeOptions) -> Result<(), AppError> {
+    if options.crf > MAX_CRF {
+        return Err(AppError::InvalidInput(format!("crf must be between 0 and {}, got {}", MAX_CRF, options.crf)));
+    }
+    if !ENCODER_PRESETS.contains(&options.preset.as_str()) {
+        return Err(AppError::InvalidInput(format!(
+            "Unknown preset '{}', expected one of: {}",
+            options.preset, ENCODER_PRESETS.join(", "),
+        )));
+    }
+    if options.max_height == Some(0) {
+        return Err(AppError::InvalidInput("max_height must be greater than zero".to_string()));
+    }
+    Ok(())
+}
+
+/// Audio bitrate assumed for target-size encodes when none is given.
+const DEFAULT_TARGET_AUDIO_BITRATE: u64 = 128_000;
+
+/// Share of a target size kept free for container overhead (headers, sample tables).
+const TARGET_SIZE_MUX_OVERHEAD: f64 = 0.02;
+
+/// Video bitrates below this wouldn't be watchable, so such targets are refused.
+const MIN_TARGET_VIDEO_BITRATE: u64 = 100_000;
+
+/// Settings for a two-pass encode that fits a video into a given size. Bitrates are in bits
+/// per second. The pass arguments go after `-i <input>`; the first pass discards its output,
+/// the second is followed by the output path.
+#[derive(Debug, Serialize, Clone)]
+pub struct TargetBitrate {
+    pub duration: f64,
+    pub target_bytes: u64,
+    pub video_bitrate: u64,
+    pub audio_bitrate: u64,
+    pub first_pass_args: Vec<String>,
+    pub second_pass_args: Vec<String>,
+}
+
+fn target_bitrate(duration: f64, target_bytes: u64, options: &TranscodeOptions) -> Result<TargetBitrate, AppError> {
+    if duration <= 0.0 {
+        return Err(AppError::InvalidInput("Cannot compute a target bitrate without a duration".to_string()));
+    }
+
+    let audio_bitrate = options.audio_bitrate.unwrap_or(DEFAULT_TARGET_AUDIO_BITRATE);
+    let total_bitrate = target_bytes as f64 * 8.0 * (1.0 - TARGET_SIZE_MUX_OVERHEAD) / duration;
+    let video_bitrate = (total_bitrate - audio_bitrate as f64).max(0.0) as u64;
+    if video_bitrate < MIN_TARGET_VIDEO_BITRATE {
+        return Err(AppError::InvalidInput(format!(
+            "{} bytes is too small for {:.0} seconds of video (would need {} kbps of video)",
+            target_bytes, duration, video_bitrate / 1000,
+        )));
+    }
+
+    let mut video_args: Vec<String> = vec!["-c:v".into(), options.video_codec.clone()];
+    if let Some(filter) = &options.video_filter {
+        video_args.extend(["-vf".into(), filter.clone()]);
+    }
+    video_args.extend(["-preset".into(), options.preset.clone(), "-b:v".into(), video_bitrate.to_string()]);
+
+    let mut first_pass_args = video_args.clone();
+    first_pass_args.extend(["-pass", "1", "-an", "-f", "null", "-"].map(String::from));
+
+    let mut second_pass_args = video_args;
+    second_pass_args.extend(["-pass".into(), "2".into()]);
+    second_pass_args.extend(["-c:a".into(), options.audio_codec.clone(), "-b:a".into(), audio_bitrate.to_string()]);
+    if options.faststart {
+        second_pass_args.extend(["-movflags", "+faststart"].map(String::from));
+    }
+
+    Ok(TargetBitrate {
+        duration,
+        target_bytes,
+        video_bitrate,
+        audio_bitrate,
+        first_pass_args,
+        second_pass_args,
+    })
+}
+
+/// Works out the video bitrate needed for the stored video to come out at `target_bytes`,
+/// along with the matching two-pass H.264 + AAC ffmpeg arguments.
 #[tauri::command]
-async fn transcode_video_for_web(input_path: String) -> Result<String, String> {
-    use std::process::Command;
+async fn compute_target_bitrate(db: State<'_, Database>, video_id: String, target_bytes: u64, audio_bitrate: Option<u64>) -> Result<TargetBitrate, AppError> {
+    let video = database::video_by_id(&db.conn(), &video_id)?;
+    let duration = video.duration.ok_or("Video has no known duration")?;
+    let options = TranscodeOptions { audio_bitrate, ..Default::default() };
+    target_bitrate(duration, target_bytes, &options)
+}
+
+fn stored_video(app: &AppHandle, input_path: &str) -> Result<Option<VideoMetadata>, AppError> {
+    Ok(database::query_videos(
+        &app.state::<Database>().conn(),
+        "WHERE full_path = ?1",
+        [database::normalize_path(input_path)],
+    )?.pop())
+}
+
+/// Duration of `input_path` in seconds, from the library when the file is indexed and from
+/// ffprobe otherwise.
+async fn source_duration(app: &AppHandle, input_path: &str) -> Result<f64, AppError> {
+    match stored_video(app, input_path)?.and_then(|video| video.duration) {
+        Some(duration) => Ok(duration),
+        None => extract_ffmpeg_metadata(std::path::Path::new(input_path), DEFAULT_PROBE_TIMEOUT).await?
+            .duration
+            .ok_or_else(|| AppError::Other("Could not determine the video duration".to_string())),
+    }
+}
+
+/// Displayed height of `input_path`, looked up like `source_duration`. ffmpeg scales the
+/// rotated picture, so this is the height after rotation.
+async fn source_height(app: &AppHandle, input_path: &str) -> Result<u32, AppError> {
+    let stored = stored_video(app, input_path)?.and_then(|video| video.display_height.or(video.height));
+    match stored {
+        Some(height) => Ok(height),
+        None => {
+            let info = extract_ffmpeg_metadata(std::path::Path::new(input_path), DEFAULT_PROBE_TIMEOUT).await?;
+            info.display_height.or(info.height).ok_or_else(|| AppError::Other("Could not determine the video height".to_string()))
+        }
+    }
+}
+
+/// `options` for `input_path` with `max_height` turned into a scale filter ahead of any
+/// `video_filter`, or dropped when the source isn't taller.
+async fn scaled_options(app: &AppHandle, input_path: &str, options: &TranscodeOptions) -> Result<TranscodeOptions, AppError> {
+    let mut options = options.clone();
+    if let Some(max_height) = options.max_height.take() {
+        if source_height(app, input_path).await? > max_height {
+            let scale = format!("scale=-2:{}", max_height);
+            options.video_filter = Some(match options.video_filter {
+                Some(filter) => format!("{},{}", scale, filter),
+                None => scale,
+            });
+        }
+    }
+    Ok(options)
+}
+
+/// Target-size settings for `input_path` if `options` asks for them.
+async fn transcode_target(app: &AppHandle, input_path: &str, options: &TranscodeOptions) -> Result<Option<TargetBitrate>, AppError> {
+    let Some(target_bytes) = options.target_size else {
+        return Ok(None);
+    };
+    let duration = source_duration(app, input_path).await?;
+    target_bitrate(duration, target_bytes, options).map(Some)
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TranscodeValidation {
+    pub ok: bool,
+    pub unsupported: Vec<String>,
+}
+
+/// Parses the name column out of `ffmpeg -encoders` / `ffmpeg -filters` listings.
+/// Both print a legend (`V..... = Video`) followed by ` <flags> <name> <description>` rows.
+fn parse_ffmpeg_listing(listing: &str) -> std::collections::HashSet<String> {
+    listing.lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let flags = parts.next()?;
+            let name = parts.next()?;
+            if name == "=" || flags.starts_with("---") {
+                None
+            } else {
+                Some(name.to_string())
+            }
+        })
+        .collect()
+}
+
+/// Extracts the filter names from a filtergraph such as `[in]scale=-2:720,fps=30[out]`.
+fn filter_names(filtergraph: &str) -> Vec<String> {
+    filtergraph.split([',', ';'])
+        .map(|f| {
+            // Drop leading pad labels, then cut at the argument list or trailing labels.
+            let mut f = f.trim();
+            while let Some(rest) = f.strip_prefix('[') {
+                f = rest.split_once(']').map(|(_, r)| r.trim_start()).unwrap_or("");
+            }
+            f.split(['=', '[']).next().unwrap_or("").trim().to_string()
+        })
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+async fn ffmpeg_listing(flag: &str) -> Result<std::collections::HashSet<String>, AppError> {
+    let mut command = Command::new(ffmpeg_paths::ffmpeg());
+    command.args(["-hide_banner", flag]);
+    let output = output_with_timeout(&mut command, "ffmpeg", DEFAULT_PROBE_TIMEOUT).await?;
     
-    let output_path = format!("{}.web.mp4", input_path);
+    if !output.status.success() {
+        return Err(AppError::FfmpegFailed(ffmpeg_failure(&format!("ffmpeg {} failed", flag), &String::from_utf8_lossy(&output.stderr))));
+    }
     
-    let output = Command::new("ffmpeg")
-        .args([
-            "-i", &input_path,
-            "-c:v", "libx264",  // H.264 for broad compatibility
-            "-c:a", "aac",      // AAC audio
-            "-preset", "fast",   // Fast encoding
-            "-crf", "23",        // Good quality
-            "-movflags", "+faststart", // Web optimization
-            &output_path
-        ])
-        .output()
-        .map_err(|e| e.to_string())?;
+    Ok(parse_ffmpeg_listing(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Checks the requested encoders and filters against what the installed ffmpeg build supports.
+#[tauri::command]
+async fn validate_transcode_options(options: TranscodeOptions) -> Result<TranscodeValidation, AppError> {
+    let encoders = ffmpeg_listing("-encoders").await?;
+    let mut unsupported = Vec::new();
     
-    if !output.status.success() {
-        return Err("Transcoding failed".to_string());
+    for (kind, codec) in [("video encoder", &options.video_codec), ("audio encoder", &options.audio_codec)] {
+        // "copy" is a stream-copy pseudo codec, not an encoder.
+        if codec != "copy" && !encoders.contains(codec) {
+            unsupported.push(format!("{} '{}'", kind, codec));
+        }
+    }
+    
+    if let Some(filtergraph) = &options.video_filter {
+        let filters = ffmpeg_listing("-filters").await?;
+        for name in filter_names(filtergraph) {
+            if !filters.contains(&name) {
+                unsupported.push(format!("filter '{}'", name));
+            }
+        }
+    }
+    
+    Ok(TranscodeValidation {
+        ok: unsupported.is_empty(),
+        unsupported,
+    })
+}
+
+/// Validates `options` and turns any unsupported entries into a single error.
+async fn ensure_transcode_options_supported(options: &TranscodeOptions) -> Result<(), AppError> {
+    check_transcode_settings(options)?;
+    let validation = validate_transcode_options(options.clone()).await?;
+    if validation.ok {
+        Ok(())
+    } else {
+        Err(AppError::InvalidInput(format!("Installed ffmpeg does not support: {}", validation.unsupported.join(", "))))
+    }
+}
+
+/// Payload of the "transcode-progress" event.
+#[derive(Debug, Serialize, Clone)]
+pub struct TranscodeProgress {
+    pub job_id: String,
+    pub input_path: String,
+    /// 0-100 over all passes, against the source duration.
+    pub percent: f64,
+}
+
+fn emit_transcode_progress(app: &AppHandle, job_id: &str, input_path: &str, percent: f64) {
+    let _ = app.emit("transcode-progress", TranscodeProgress {
+        job_id: job_id.to_string(),
+        input_path: input_path.to_string(),
+        percent: percent.clamp(0.0, 100.0),
+    });
+}
+
+/// Transcodes `input_path` to a web-playable MP4 next to it and returns the output path.
+/// Progress is sent as "transcode-progress" events tagged with `job_id` (the input path when
+/// not given), so several transcodes can report independently.
+#[tauri::command]
+async fn transcode_video_for_web(app: AppHandle, input_path: String, options: Option<TranscodeOptions>, job_id: Option<String>) -> Result<String, AppError> {
+    let options = options.unwrap_or_default();
+    ensure_transcode_options_supported(&options).await?;
+    let options = scaled_options(&app, &input_path, &options).await?;
+    let target = transcode_target(&app, &input_path, &options).await?;
+    let job_id = job_id.unwrap_or_else(|| input_path.clone());
+    run_transcode(&app, &job_id, &input_path, &options, target.as_ref()).await
+}
+
+/// Transcodes `input_path` into an HLS stream in `output_dir`: `playlist.m3u8` plus 6-second
+/// `.ts` segments, so long files can be played progressively. Returns the playlist path.
+/// Takes the same options as `transcode_video_for_web` except `target_size`, and reports
+/// progress through "transcode-progress" events the same way. Waits its turn in the same
+/// transcode queue, and `cancel_transcode` stops it and removes the partial playlist and
+/// segments.
+#[tauri::command]
+async fn transcode_to_hls(app: AppHandle, input_path: String, output_dir: String, options: Option<TranscodeOptions>, job_id: Option<String>) -> Result<String, AppError> {
+    let options = options.unwrap_or_default();
+    if options.target_size.is_some() {
+        return Err(AppError::InvalidInput("target_size is not supported for HLS output".to_string()));
+    }
+    ensure_transcode_options_supported(&options).await?;
+    let options = scaled_options(&app, &input_path, &options).await?;
+    let job_id = job_id.unwrap_or_else(|| input_path.clone());
+    
+    let output_dir = std::path::Path::new(&output_dir);
+    let playlist_path = output_dir.join("playlist.m3u8").to_string_lossy().to_string();
+    let segment_pattern = output_dir.join("segment_%05d.ts").to_string_lossy().to_string();
+    
+    let crf = options.crf.to_string();
+    let mut args: Vec<&str> = vec![
+        "-i", &input_path,
+        "-c:v", &options.video_codec,
+        "-c:a", &options.audio_codec,
+    ];
+    if let Some(filter) = &options.video_filter {
+        args.extend(["-vf", filter.as_str()]);
     }
+    args.extend([
+        "-preset", &options.preset,
+        "-crf", &crf,
+        "-f", "hls",
+        "-hls_time", "6",
+        "-hls_playlist_type", "vod",
+        "-hls_segment_filename", &segment_pattern,
+        "-y",
+        &playlist_path,
+    ]);
     
+    let duration = source_duration(&app, &input_path).await.ok().filter(|d| *d > 0.0);
+    let report = |out_time: f64| {
+        if let Some(duration) = duration {
+            emit_transcode_progress(&app, &job_id, &input_path, out_time / duration * 100.0);
+        }
+    };
+    let (app_ref, input, playlist) = (&app, input_path.as_str(), playlist_path.as_str());
+    let (result, started) = queued_transcode(&app, &job_id, &input_path, |cancel| async move {
+        fs::create_dir_all(output_dir)?;
+        run_ffmpeg_logged(app_ref, input, &args, "HLS transcoding failed", report, Some(&cancel)).await?;
+        Ok(playlist.to_string())
+    }).await;
+    // Only the playlist and segments are removed, the directory too if that leaves it empty.
+    if started && matches!(result, Err(AppError::Cancelled(_))) {
+        for entry in fs::read_dir(output_dir).into_iter().flatten().flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name == "playlist.m3u8" || (name.starts_with("segment_") && name.ends_with(".ts")) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+        let _ = fs::remove_dir(output_dir);
+    }
+    result
+}
+
+/// Video codecs an MP4 can carry as-is for the webview.
+const REMUXABLE_VIDEO_CODECS: &[&str] = &["h264", "hevc"];
+
+/// Whether `remux_to_mp4` takes a file with these streams: H.264/HEVC video and AAC or no audio.
+fn remuxable_to_mp4(video_codec: Option<&str>, audio_codec: Option<&str>, audio_track_count: u32) -> bool {
+    video_codec.is_some_and(|codec| REMUXABLE_VIDEO_CODECS.contains(&codec))
+        && (audio_track_count == 0 || audio_codec == Some("aac"))
+}
+
+/// Copies the first video and audio stream of `input_path` into an MP4 next to it (the same
+/// output path as `transcode_video_for_web`) without re-encoding, which takes seconds instead
+/// of a full transcode. Only for H.264/HEVC video with AAC (or no) audio; anything else needs
+/// `transcode_video_for_web`. Returns the output path.
+#[tauri::command]
+async fn remux_to_mp4(app: AppHandle, input_path: String) -> Result<String, AppError> {
+    let info = extract_ffmpeg_metadata(std::path::Path::new(&input_path), DEFAULT_PROBE_TIMEOUT).await?;
+    let video_codec = info.codec.as_deref().unwrap_or("unknown");
+    if !remuxable_to_mp4(info.codec.as_deref(), info.audio_codec.as_deref(), info.audio_track_count) {
+        return Err(AppError::InvalidInput(format!(
+            "Can't remux {} video with {} audio to MP4 without re-encoding, use transcode_video_for_web instead",
+            video_codec, info.audio_codec.as_deref().unwrap_or("unknown"),
+        )));
+    }
+
+    let output_path = format!("{}.web.mp4", input_path);
+    // Explicit maps keep subtitle and data streams MP4 can't hold from failing the copy.
+    let mut args: Vec<&str> = vec![
+        "-i", &input_path,
+        "-map", "0:v:0",
+        "-map", "0:a:0?",
+        "-c", "copy",
+    ];
+    // Apple's decoders (and so WebKit webviews) only play HEVC in MP4 tagged as hvc1.
+    if video_codec == "hevc" {
+        args.extend(["-tag:v", "hvc1"]);
+    }
+    args.extend(["-movflags", "+faststart", "-y", &output_path]);
+
+    if let Err(e) = run_ffmpeg_logged(&app, &input_path, &args, "Remuxing failed", |_| {}, None).await {
+        let _ = fs::remove_file(&output_path);
+        return Err(e);
+    }
     Ok(output_path)
 }
 
+#[derive(Debug, Serialize, Clone)]
+pub struct BatchTranscodeResult {
+    pub input_path: String,
+    pub output_path: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Transcodes several files with the same options. The options are validated once up front,
+/// so an unsupported encoder or filter is rejected before any file is touched. All files are
+/// queued at once, so up to `transcode_concurrency` of them run in parallel.
+#[tauri::command]
+async fn transcode_videos_for_web(app: AppHandle, input_paths: Vec<String>, options: Option<TranscodeOptions>) -> Result<Vec<BatchTranscodeResult>, AppError> {
+    let options = options.unwrap_or_default();
+    ensure_transcode_options_supported(&options).await?;
+
+    let jobs = input_paths.into_iter().map(|input_path| {
+        let (app, options) = (&app, &options);
+        async move {
+            let result = async {
+                let options = scaled_options(app, &input_path, options).await?;
+                let target = transcode_target(app, &input_path, &options).await?;
+                run_transcode(app, &input_path, &input_path, &options, target.as_ref()).await
+            }.await;
+            match result {
+                Ok(output_path) => BatchTranscodeResult { input_path, output_path: Some(output_path), error: None },
+                Err(e) => BatchTranscodeResult { input_path, output_path: None, error: Some(e.to_string()) },
+            }
+        }
+    });
+    Ok(futures::future::join_all(jobs).await)
+}
+
+/// Cancellation signals of the running transcodes, by job id.
+#[derive(Default)]
+pub struct TranscodeCancellations(Mutex<std::collections::HashMap<String, Arc<Notify>>>);
+
+impl TranscodeCancellations {
+    fn signals(&self) -> MutexGuard<'_, std::collections::HashMap<String, Arc<Notify>>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Message of the `Cancelled` error of a transcode stopped through `cancel_transcode`.
+const TRANSCODE_CANCELLED: &str = "Transcode cancelled";
+
+/// Stops the transcode running as `job_id` and deletes its partial output, or takes it out of
+/// the queue if it hasn't started yet. Does nothing if the job already finished.
+#[tauri::command]
+async fn cancel_transcode(cancellations: State<'_, TranscodeCancellations>, queue: State<'_, transcode_queue::TranscodeQueue>, job_id: String) -> Result<(), AppError> {
+    if queue.dequeue(&job_id) {
+        return Ok(());
+    }
+    if let Some(signal) = cancellations.signals().get(&job_id) {
+        // Stores a permit, so a cancel between the two passes of a target-size encode still lands.
+        signal.notify_one();
+    }
+    Ok(())
+}
+
+/// Runs the transcode as `job_id` once the transcode queue lets it start, cancellable through
+/// `cancel_transcode` while waiting and while running.
+async fn run_transcode(app: &AppHandle, job_id: &str, input_path: &str, options: &TranscodeOptions, target: Option<&TargetBitrate>) -> Result<String, AppError> {
+    let (result, started) = queued_transcode(app, job_id, input_path, |cancel| async move {
+        transcode_passes(app, job_id, input_path, options, target, &cancel).await
+    }).await;
+    // A job dequeued before starting wrote nothing; the file may be an earlier transcode.
+    if started && matches!(result, Err(AppError::Cancelled(_))) {
+        let _ = fs::remove_file(format!("{}.web.mp4", input_path));
+    }
+    result
+}
+
+/// Runs `job` once the transcode queue gives `job_id` a slot, with a signal `cancel_transcode`
+/// can stop it through. Also returns whether the job started, so callers know whether there is
+/// partial output to clean up.
+async fn queued_transcode<F, Fut>(app: &AppHandle, job_id: &str, input_path: &str, job: F) -> (Result<String, AppError>, bool)
+where
+    F: FnOnce(Arc<Notify>) -> Fut,
+    Fut: std::future::Future<Output = Result<String, AppError>>,
+{
+    let cancel = Arc::new(Notify::new());
+    let cancellations = app.state::<TranscodeCancellations>();
+    // Registered before queueing, so a cancel right as the job starts still reaches it.
+    cancellations.signals().insert(job_id.to_string(), cancel.clone());
+    
+    let (result, started) = match transcode_queue::wait_for_slot(app, job_id, input_path, TRANSCODE_CANCELLED).await {
+        Ok(slot) => {
+            let result = job(cancel.clone()).await;
+            drop(slot);
+            transcode_queue::report_finished(app, job_id, input_path, &result);
+            (result, true)
+        }
+        Err(e) => (Err(e), false),
+    };
+    
+    // A second transcode may have been started under the same id in the meantime; leave its signal.
+    {
+        let mut signals = cancellations.signals();
+        if signals.get(job_id).is_some_and(|signal| Arc::ptr_eq(signal, &cancel)) {
+            signals.remove(job_id);
+        }
+    }
+    (result, started)
+}
+
+async fn transcode_passes(app: &AppHandle, job_id: &str, input_path: &str, options: &TranscodeOptions, target: Option<&TargetBitrate>, cancel: &Notify) -> Result<String, AppError> {
+    let output_path = format!("{}.web.mp4", input_path);
+    
+    // Without a duration there is nothing to measure against, the log events still flow.
+    let duration = source_duration(app, input_path).await.ok().filter(|d| *d > 0.0);
+    let pass_count = if target.is_some() { 2.0 } else { 1.0 };
+    let report = |pass: f64, out_time: f64| {
+        if let Some(duration) = duration {
+            let percent = (pass + (out_time / duration).min(1.0)) / pass_count * 100.0;
+            emit_transcode_progress(app, job_id, input_path, percent);
+        }
+    };
+    
+    let Some(target) = target else {
+        let crf = options.crf.to_string();
+        let mut args: Vec<&str> = vec![
+            "-i", input_path,
+            "-c:v", &options.video_codec,
+            "-c:a", &options.audio_codec,
+        ];
+        if let Some(filter) = &options.video_filter {
+            args.extend(["-vf", filter.as_str()]);
+        }
+        args.extend(["-preset", &options.preset, "-crf", &crf]);
+        if options.faststart {
+            args.extend(["-movflags", "+faststart"]);
+        }
+        args.push(&output_path);
+        run_ffmpeg_logged(app, input_path, &args, "Transcoding failed", |out_time| report(0.0, out_time), Some(cancel)).await?;
+        return Ok(output_path);
+    };
+    
+    // Each file gets its own pass log so concurrent transcodes don't read each other's stats.
+    let passlog_name = {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        input_path.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    };
+    let passlog_dir = std::env::temp_dir().join("shadowcrawler_passlogs");
+    fs::create_dir_all(&passlog_dir)?;
+    let passlog = passlog_dir.join(&passlog_name).to_string_lossy().to_string();
+    
+    let passes = [
+        (&target.first_pass_args, None, "First pass failed"),
+        (&target.second_pass_args, Some(output_path.as_str()), "Second pass failed"),
+    ];
+    let mut result = Ok(());
+    for (pass, (pass_args, output, context)) in passes.into_iter().enumerate() {
+        let mut args = vec!["-i", input_path, "-passlogfile", &passlog];
+        args.extend(pass_args.iter().map(String::as_str));
+        args.extend(output);
+        result = run_ffmpeg_logged(app, input_path, &args, context, |out_time| report(pass as f64, out_time), Some(cancel)).await;
+        if result.is_err() {
+            break;
+        }
+    }
+    
+    // ffmpeg names the stats files after the prefix (e.g. `<prefix>-0.log`, `<prefix>-0.log.mbtree`).
+    for entry in fs::read_dir(&passlog_dir).into_iter().flatten().flatten() {
+        if entry.file_name().to_string_lossy().starts_with(&passlog_name) {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+    
+    result.map(|_| output_path)
+}
+
+/// Reads the output position in seconds from an ffmpeg status line such as
+/// `frame=  120 fps= 60 ... time=00:00:04.00 bitrate=...`. `time=N/A` gives `None`.
+fn parse_ffmpeg_time(line: &str) -> Option<f64> {
+    let (_, rest) = line.split_once("time=")?;
+    let time = rest.split_whitespace().next()?;
+    let mut seconds = 0.0;
+    for part in time.split(':') {
+        seconds = seconds * 60.0 + part.parse::<f64>().ok()?;
+    }
+    Some(seconds)
+}
+
+/// Runs ffmpeg with `args`, forwarding its log to the UI as `ffmpeg-log` events and calling
+/// `on_time` with the output position from each status line instead. On failure the error is `context`
+/// followed by the last lines ffmpeg printed.
+async fn run_ffmpeg_logged(app: &AppHandle, input_path: &str, args: &[&str], context: &str, mut on_time: impl FnMut(f64), cancel: Option<&Notify>) -> Result<(), AppError> {
+    use std::collections::VecDeque;
+    use std::process::Stdio;
+    use tokio::io::{AsyncBufReadExt, BufReader};
+    
+    let mut child = Command::new(ffmpeg_paths::ffmpeg())
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| AppError::spawn("ffmpeg", e))?;
+    
+    // Forward stderr to the UI line by line while keeping the tail for the error message.
+    let mut tail = VecDeque::with_capacity(FFMPEG_ERROR_TAIL_LINES);
+    let stderr = child.stderr.take();
+    let forward = async {
+        let Some(stderr) = stderr else { return };
+        // ffmpeg separates progress updates with '\r', so split on both line endings.
+        let mut chunks = BufReader::new(stderr).split(b'\n');
+        while let Ok(Some(chunk)) = chunks.next_segment().await {
+            for line in String::from_utf8_lossy(&chunk).split('\r') {
+                let line = line.trim_end();
+                if line.is_empty() {
+                    continue;
+                }
+                // Status lines come several times a second and only feed `on_time`.
+                match parse_ffmpeg_time(line) {
+                    Some(out_time) => on_time(out_time),
+                    None => {
+                        let _ = app.emit("ffmpeg-log", FfmpegLogLine {
+                            input_path: input_path.to_string(),
+                            line: line.to_string(),
+                        });
+                    }
+                }
+                if tail.len() == FFMPEG_ERROR_TAIL_LINES {
+                    tail.pop_front();
+                }
+                tail.push_back(line.to_string());
+            }
+        }
+    };
+    
+    // stderr closes when ffmpeg exits, so forwarding it covers the whole run.
+    let cancelled = match cancel {
+        Some(cancel) => tokio::select! {
+            _ = forward => false,
+            _ = cancel.notified() => true,
+        },
+        None => {
+            forward.await;
+            false
+        }
+    };
+    if cancelled {
+        let _ = child.kill().await;
+        return Err(AppError::Cancelled(TRANSCODE_CANCELLED.to_string()));
+    }
+    
+    let status = child.wait().await?;
+    
+    if !status.success() {
+        let stderr = Vec::from(tail).join("\n");
+        return Err(AppError::FfmpegFailed(ffmpeg_failure(context, &stderr)));
+    }
+    
+    Ok(())
+}
+
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    logging::init();
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_sql::Builder::default().build())
+        .register_asynchronous_uri_scheme_protocol(video_stream::SCHEME, video_stream::handle)
+        .setup(|app| {
+            let db_path = app.path().app_data_dir()?.join("shadowcrawler").join("library.db");
+            let db = Database::open(&db_path)?;
+            ffmpeg_paths::load(&db.conn());
+            logging::load(&db.conn());
+            app.manage(db);
+            app.manage(operations::ActiveOperation::default());
+            app.manage(pulled_crawls::PulledCrawls::default());
+            app.manage(CrawlCancellations::default());
+            app.manage(TranscodeCancellations::default());
+            app.manage(transcode_queue::TranscodeQueue::default());
+            app.manage(watcher::Watchers::default());
+            app.manage(video_stream::start_server(app.handle())?);
+            roots::index_on_startup(app.handle());
+            thumbnail_cache::enforce_limit_in_background(app.handle());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             crawl_directory,
+            crawl_directories,
+            crawl_directory_report,
+            scan_directory_summary,
+            count_videos,
+            validate_crawl_target,
+            get_default_video_extensions,
             get_thumbnail_data,
+            generate_thumbnail,
+            generate_motion_thumbnail,
+            generate_thumbnails_since,
+            find_videos_without_leading_keyframe,
             get_video_data,
             init_video_database,
             get_videos_from_database,
+            get_videos_paged,
+            search_videos,
+            get_videos,
+            set_rating,
+            toggle_favorite,
             get_videos_by_folder,
+            get_videos_by_bitrate,
+            find_container_mismatches,
+            get_videos_by_day,
+            get_videos_by_encoder,
+            get_videos_added_between,
             get_folders,
             insert_video_record,
             clear_video_database,
             index_directory,
+            audit_library,
+            compute_target_bitrate,
+            move_video,
+            delete_video,
+            get_folder_bitrate_stats,
+            get_library_stats,
+            quick_integrity_check,
+            pulled_crawls::start_crawl,
+            pulled_crawls::poll_crawl_results,
+            pulled_crawls::release_crawl,
+            cancel_crawl,
+            split_recordings::detect_split_recordings,
+            split_recordings::concat_split_recording,
+            series::check_series_completeness,
+            content_hash::hash_video_content,
+            content_hash::get_folder_videos_deduplicated,
+            content_hash::find_duplicates,
+            get_view_state,
+            get_shuffled_order,
+            find_videos_above_resolution,
+            get_dominant_aspect_ratio,
+            get_folder_dominant_aspect_ratio,
+            find_aspect_outliers,
+            recompute_decode_difficulty,
+            normalize_stored_paths,
+            reprobe_videos,
+            reprobe_by_codec,
+            probe_video,
+            animated_preview::generate_animated_preview,
+            audio::analyze_audio_levels,
+            audio::analyze_folder_audio_levels,
+            audio::generate_waveform,
+            ffmpeg_paths::set_ffmpeg_paths,
+            ffmpeg_paths::check_ffmpeg_available,
+            gallery::export_html_gallery,
+            library_file::export_library,
+            library_file::import_library,
+            logging::get_log_level,
+            logging::set_log_level,
+            motion::analyze_camera_motion,
+            operations::begin_operation,
+            operations::end_operation,
+            operations::list_operations,
+            operations::undo_operation,
+            playback::get_playback_capabilities,
+            playback::set_playback_capabilities,
+            playback::recompute_web_readiness,
+            playback::is_web_playable,
+            power::get_power_state,
+            power::get_battery_policy,
+            power::set_battery_policy,
+            roots::get_registered_roots,
+            roots::add_root,
+            roots::remove_root,
+            roots::get_index_roots_on_startup,
+            roots::set_index_roots_on_startup,
+            roots::index_registered_roots,
+            storyboard::generate_storyboard,
+            subtitles::extract_subtitles,
+            sync::sync_manifest,
+            tags::import_tags_csv,
+            tags::add_tag,
+            tags::remove_tag,
+            tags::get_tags,
+            tags::list_all_tags,
+            tags::get_videos_by_tag,
+            thumbnail_cache::get_thumbnail_cache_size,
+            thumbnail_cache::set_thumbnail_cache_limit,
+            thumbnail_cache::clear_thumbnail_cache,
+            transcode_queue::get_transcode_queue,
+            transcode_queue::get_transcode_concurrency,
+            transcode_queue::set_transcode_concurrency,
+            video_stream::get_stream_url,
+            watcher::watch_directory,
+            watcher::stop_watching,
+            set_view_state,
             read_network_file,
             stream_network_file,
             stream_network_file_chunk,
-            transcode_video_for_web
+            read_file_range,
+            transcode_video_for_web,
+            transcode_videos_for_web,
+            cancel_transcode,
+            transcode_to_hls,
+            remux_to_mp4,
+            validate_transcode_options
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `contents` to a file in the temp directory named after `name` and the process.
+    fn temp_file(name: &str, contents: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("shadowcrawler-{}-{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn chunk_at_or_past_the_end_is_empty_and_complete() {
+        let path = temp_file("chunk-end", b"0123456789");
+        for offset in [10, 25] {
+            let chunk = read_chunk(&path.to_string_lossy(), offset, 4).await.unwrap();
+            assert!(chunk.data.is_empty());
+            assert!(chunk.is_complete);
+            assert_eq!(chunk.offset, offset);
+            assert_eq!(chunk.total_size, 10);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn range_start_after_end_is_rejected() {
+        let path = temp_file("range-reversed", b"0123456789");
+        let result = read_range(&path.to_string_lossy(), 6, 2).await;
+        assert!(matches!(result, Err(AppError::InvalidInput(_))));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn range_end_past_the_file_is_clamped() {
+        let path = temp_file("range-clamped", b"0123456789");
+        let range = read_range(&path.to_string_lossy(), 4, 100).await.unwrap();
+        assert_eq!(range.data, b"456789");
+        assert_eq!((range.start, range.end, range.total_size), (4, 9, 10));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,137 @@
+// Export of the whole library to a CSV or JSON file, for spreadsheets and other tools, and
+// import of JSON exports to seed another install without probing every file again.
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+use tauri::State;
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+use crate::{TimestampFormat, VideoMetadata};
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub enum ExportFormat {
+    /// One row per video with a header row of `VideoMetadata` field names. Unknown values are
+    /// empty cells, lists are JSON.
+    Csv,
+    /// An array of `VideoMetadata` objects.
+    Json,
+}
+
+/// Writes every video in the library to `out_path`, with every `VideoMetadata` field as a
+/// column or key. Dates are epoch millis unless `timestamp_format` says otherwise. Returns the
+/// number of videos written.
+#[tauri::command]
+pub async fn export_library(
+    db: State<'_, Database>,
+    format: ExportFormat,
+    out_path: String,
+    timestamp_format: Option<TimestampFormat>,
+) -> Result<u64, AppError> {
+    let mut videos = database::query_videos(&db.conn(), "ORDER BY full_path", [])?;
+    if let Some(timestamp_format) = timestamp_format {
+        for video in &mut videos {
+            video.set_timestamp_format(timestamp_format);
+        }
+    }
+
+    let file = File::create(&out_path)?;
+    match format {
+        ExportFormat::Csv => {
+            let columns: Vec<&str> = database::VIDEO_COLUMNS.split(',').map(str::trim).collect();
+            let mut writer = csv::Writer::from_writer(file);
+            writer.write_record(&columns).map_err(|e| format!("Failed to write CSV: {}", e))?;
+            for video in &videos {
+                let fields = serde_json::to_value(video).map_err(|e| format!("Failed to write CSV: {}", e))?;
+                writer
+                    .write_record(columns.iter().map(|column| csv_cell(&fields[*column])))
+                    .map_err(|e| format!("Failed to write CSV: {}", e))?;
+            }
+            writer.flush()?;
+        }
+        ExportFormat::Json => {
+            let mut writer = BufWriter::new(file);
+            serde_json::to_writer_pretty(&mut writer, &videos).map_err(|e| format!("Failed to write JSON: {}", e))?;
+            writer.flush()?;
+        }
+    }
+    Ok(videos.len() as u64)
+}
+
+/// One CSV cell; lists such as `subtitle_tracks` are written as JSON.
+fn csv_cell(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub enum MergeMode {
+    /// Clear the library first so it holds exactly the imported videos.
+    Replace,
+    /// Insert new videos and update the ones already in the library (by full path), including
+    /// their rating and favorite flag, which re-indexing leaves alone.
+    Upsert,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct ImportReport {
+    pub inserted: u64,
+    pub updated: u64,
+    /// Videos left out because their file doesn't exist on this machine.
+    pub skipped: u64,
+    pub skipped_paths: Vec<String>,
+}
+
+/// Imports a JSON export from `export_library` in one transaction. Videos whose file doesn't
+/// exist are skipped unless `keep_missing` is set, e.g. for a library on a drive that isn't
+/// connected yet. Tags aren't part of the export, so Replace leaves the library untagged.
+#[tauri::command]
+pub async fn import_library(
+    db: State<'_, Database>,
+    path: String,
+    mode: MergeMode,
+    keep_missing: Option<bool>,
+) -> Result<ImportReport, AppError> {
+    let file = File::open(&path)?;
+    let videos: Vec<VideoMetadata> = serde_json::from_reader(BufReader::new(file))
+        .map_err(|e| AppError::InvalidInput(format!("Not a library export: {}", e)))?;
+    let keep_missing = keep_missing.unwrap_or(false);
+
+    let mut conn = db.conn();
+    let tx = conn.transaction()?;
+    if let MergeMode::Replace = mode {
+        tx.execute("DELETE FROM videos", [])?;
+    }
+
+    let mut report = ImportReport::default();
+    for video in &videos {
+        if !keep_missing && !Path::new(&video.full_path).exists() {
+            report.skipped += 1;
+            report.skipped_paths.push(video.full_path.clone());
+            continue;
+        }
+        let exists = tx.query_row(
+            "SELECT 1 FROM videos WHERE full_path = ?1",
+            [database::normalize_path(&video.full_path)],
+            |_| Ok(()),
+        ).is_ok();
+        database::upsert_video(&tx, video)?;
+        if exists {
+            // The upsert keeps the stored curation, but an import should carry over the exported one.
+            tx.execute(
+                "UPDATE videos SET rating = ?1, is_favorite = ?2 WHERE full_path = ?3",
+                rusqlite::params![video.rating, video.is_favorite, database::normalize_path(&video.full_path)],
+            )?;
+            report.updated += 1;
+        } else {
+            report.inserted += 1;
+        }
+    }
+    tx.commit()?;
+    Ok(report)
+}
@@ -0,0 +1,81 @@
+// Leveled logging to stderr through the `log` facade. Only warnings and errors are written by
+// default; `set_log_level` turns on the chattier levels while debugging.
+
+use log::{LevelFilter, Log, Metadata, Record};
+use rusqlite::Connection;
+use std::io::Write;
+use tauri::State;
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+
+/// Settings key holding the level set through `set_log_level`, applied again on startup.
+pub const LOG_LEVEL_KEY: &str = "log_level";
+
+const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::Warn;
+
+/// Writes each record as one line: time, level, module and message.
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let time = chrono::DateTime::<chrono::Utc>::from(std::time::SystemTime::now());
+        let _ = writeln!(
+            std::io::stderr().lock(),
+            "{} {:<5} {}: {}",
+            time.format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+            record.level(),
+            record.target(),
+            record.args(),
+        );
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stderr().flush();
+    }
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+/// Installs the logger at the default level; call once, before anything logs.
+pub fn init() {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(DEFAULT_LOG_LEVEL);
+    }
+}
+
+/// Applies the level saved by `set_log_level`, if any.
+pub fn load(conn: &Connection) {
+    let saved = database::get_setting::<String>(conn, LOG_LEVEL_KEY).ok().flatten();
+    if let Some(level) = saved.and_then(|name| name.parse::<LevelFilter>().ok()) {
+        log::set_max_level(level);
+    }
+}
+
+#[tauri::command]
+pub async fn get_log_level() -> Result<String, AppError> {
+    Ok(log::max_level().as_str().to_lowercase())
+}
+
+/// Sets the most verbose level written: "off", "error", "warn", "info", "debug" or "trace".
+/// Takes effect right away and is kept across restarts.
+#[tauri::command]
+pub async fn set_log_level(db: State<'_, Database>, level: String) -> Result<(), AppError> {
+    let filter = level.trim().parse::<LevelFilter>().map_err(|_| {
+        AppError::InvalidInput(format!(
+            "Unknown log level \"{}\", expected off, error, warn, info, debug or trace",
+            level,
+        ))
+    })?;
+    database::set_setting(&db.conn(), LOG_LEVEL_KEY, &filter.as_str().to_lowercase())?;
+    log::set_max_level(filter);
+    log::info!("Log level set to {}", filter);
+    Ok(())
+}
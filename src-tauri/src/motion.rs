@@ -0,0 +1,177 @@
+// Coarse camera motion classification (tripod vs. panning vs. handheld) for culling footage.
+//
+// ffmpeg decodes a short stretch of the clip into small grayscale frames; the global shift
+// between consecutive frames is found by block matching, and the sequence of shifts is
+// classified by how large and how consistent it is.
+
+use serde::Serialize;
+use std::path::Path;
+use tokio::process::Command;
+use tauri::State;
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+use crate::{ffmpeg_failure, output_with_timeout};
+
+/// Size of the analysed frames. Small enough that brute-force matching is cheap, large enough
+/// to resolve slow pans.
+const FRAME_WIDTH: usize = 96;
+const FRAME_HEIGHT: usize = 54;
+
+/// Frames analysed per second of video.
+const SAMPLE_FPS: u32 = 5;
+
+/// Seconds of video analysed, starting at 10% of the duration to skip fumbling at the start.
+const SAMPLE_SECONDS: f64 = 30.0;
+
+/// How long decoding the `SAMPLE_SECONDS` may take before ffmpeg is killed.
+const ANALYSIS_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Largest shift between two frames that is searched for, in analysed pixels.
+const MAX_SHIFT: i32 = 6;
+
+/// Average shift (analysed pixels per frame) below which the camera counts as static.
+const STATIC_SHIFT: f64 = 0.3;
+
+/// How well the shifts must agree in direction (0-1) to count as a pan rather than shake.
+const PAN_CONSISTENCY: f64 = 0.6;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MotionType {
+    Static,
+    Panning,
+    Shaky,
+}
+
+impl MotionType {
+    fn as_str(self) -> &'static str {
+        match self {
+            MotionType::Static => "static",
+            MotionType::Panning => "panning",
+            MotionType::Shaky => "shaky",
+        }
+    }
+}
+
+/// Which way the camera turns while panning.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PanDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CameraMotion {
+    pub motion_type: MotionType,
+    /// Set for `Panning`.
+    pub direction: Option<PanDirection>,
+    /// Rough 0-1 certainty of the classification; this is a heuristic.
+    pub confidence: f64,
+    /// Average movement per analysed frame as a fraction of the frame width.
+    pub average_shift: f64,
+}
+
+/// Finds the shift `(dx, dy)` that best maps `prev` onto `next`, i.e. how far the picture content
+/// moved, by minimizing the mean absolute difference over the frame interior.
+fn global_shift(prev: &[u8], next: &[u8]) -> (i32, i32) {
+    let margin = MAX_SHIFT as usize;
+    let mut best: (i32, i32) = (0, 0);
+    let mut best_cost = u64::MAX;
+    for dy in -MAX_SHIFT..=MAX_SHIFT {
+        for dx in -MAX_SHIFT..=MAX_SHIFT {
+            let mut cost = 0u64;
+            for y in margin..FRAME_HEIGHT - margin {
+                let row = y * FRAME_WIDTH;
+                let shifted_row = (y as i32 + dy) as usize * FRAME_WIDTH;
+                for x in margin..FRAME_WIDTH - margin {
+                    let shifted = shifted_row + (x as i32 + dx) as usize;
+                    cost += prev[row + x].abs_diff(next[shifted]) as u64;
+                }
+            }
+            // Prefer smaller shifts on ties, so flat frames read as static.
+            if cost < best_cost || (cost == best_cost && dx.abs() + dy.abs() < best.0.abs() + best.1.abs()) {
+                best_cost = cost;
+                best = (dx, dy);
+            }
+        }
+    }
+    best
+}
+
+fn classify(shifts: &[(i32, i32)]) -> CameraMotion {
+    let count = shifts.len().max(1) as f64;
+    let average_magnitude = shifts.iter()
+        .map(|(dx, dy)| ((dx * dx + dy * dy) as f64).sqrt())
+        .sum::<f64>() / count;
+    let mean_dx = shifts.iter().map(|(dx, _)| *dx as f64).sum::<f64>() / count;
+    let mean_dy = shifts.iter().map(|(_, dy)| *dy as f64).sum::<f64>() / count;
+    let average_shift = average_magnitude / FRAME_WIDTH as f64;
+
+    if average_magnitude < STATIC_SHIFT {
+        return CameraMotion {
+            motion_type: MotionType::Static,
+            direction: None,
+            confidence: 0.5 + 0.5 * (1.0 - average_magnitude / STATIC_SHIFT),
+            average_shift,
+        };
+    }
+
+    // 1 when every shift points the same way, near 0 when they cancel out.
+    let consistency = (mean_dx * mean_dx + mean_dy * mean_dy).sqrt() / average_magnitude;
+    if consistency >= PAN_CONSISTENCY {
+        // The picture moves opposite to the camera: content drifting left means panning right.
+        let direction = if mean_dx.abs() >= mean_dy.abs() {
+            if mean_dx < 0.0 { PanDirection::Right } else { PanDirection::Left }
+        } else if mean_dy < 0.0 {
+            PanDirection::Down
+        } else {
+            PanDirection::Up
+        };
+        CameraMotion { motion_type: MotionType::Panning, direction: Some(direction), confidence: consistency, average_shift }
+    } else {
+        CameraMotion { motion_type: MotionType::Shaky, direction: None, confidence: 1.0 - consistency, average_shift }
+    }
+}
+
+async fn measure_camera_motion(path: &Path, duration: f64) -> Result<CameraMotion, AppError> {
+    let start = duration * 0.1;
+    let filter = format!(
+        "fps={},scale={}:{}:flags=area,format=gray",
+        SAMPLE_FPS, FRAME_WIDTH, FRAME_HEIGHT,
+    );
+    let mut analysis = Command::new(crate::ffmpeg_paths::ffmpeg());
+    analysis.args(["-v", "error", "-ss", &format!("{:.2}", start), "-t", &format!("{}", SAMPLE_SECONDS), "-i"])
+        .arg(path)
+        .args(["-an", "-vf", &filter, "-f", "rawvideo", "-"]);
+    let output = output_with_timeout(&mut analysis, "ffmpeg", ANALYSIS_TIMEOUT).await?;
+    if !output.status.success() {
+        return Err(AppError::FfmpegFailed(ffmpeg_failure("Motion analysis failed", &String::from_utf8_lossy(&output.stderr))));
+    }
+
+    let frames: Vec<&[u8]> = output.stdout.chunks_exact(FRAME_WIDTH * FRAME_HEIGHT).collect();
+    if frames.len() < 2 {
+        return Err(AppError::InvalidInput("Video is too short to analyse camera motion".to_string()));
+    }
+    let shifts: Vec<(i32, i32)> = frames.windows(2).map(|pair| global_shift(pair[0], pair[1])).collect();
+    Ok(classify(&shifts))
+}
+
+/// Classifies the camera motion of a stored video as static, panning or shaky, from up to 30
+/// seconds of footage. With `store` set, the result is also saved as the video's `motion_type`.
+#[tauri::command]
+pub async fn analyze_camera_motion(db: State<'_, Database>, video_id: String, store: Option<bool>) -> Result<CameraMotion, AppError> {
+    let video = database::video_by_id(&db.conn(), &video_id)?;
+    let motion = measure_camera_motion(Path::new(&video.full_path), video.duration.unwrap_or(0.0)).await?;
+
+    if store.unwrap_or(false) {
+        db.conn().execute(
+            "UPDATE videos SET motion_type = ?1 WHERE full_path = ?2",
+            [motion.motion_type.as_str(), &video.full_path],
+        )?;
+    }
+    Ok(motion)
+}
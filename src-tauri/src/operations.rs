@@ -0,0 +1,179 @@
+// Persistent log of batch reorganizations, giving them a one-level undo.
+//
+// `begin_operation` opens an operation; until `end_operation` is called, destructive commands
+// record the before-state of every video they touch through `record_move`. `undo_operation`
+// replays those records in reverse. The log lives in the database so undo survives a restart.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::State;
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+use crate::VideoMetadata;
+
+/// The operation destructive commands currently record into, if any.
+#[derive(Default)]
+pub struct ActiveOperation(Mutex<Option<i64>>);
+
+impl ActiveOperation {
+    pub fn current(&self) -> Option<i64> {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set(&self, operation_id: Option<i64>) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = operation_id;
+    }
+}
+
+/// Records that the video `before` was moved to `new_path` as part of `operation_id`.
+pub fn record_move(conn: &Connection, operation_id: i64, before: &VideoMetadata, new_path: &str) -> Result<(), AppError> {
+    let old_row = serde_json::to_string(before).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO operation_entries (operation_id, old_path, new_path, old_row) VALUES (?1, ?2, ?3, ?4)",
+        params![operation_id, before.full_path, new_path, old_row],
+    )?;
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct OperationSummary {
+    pub id: i64,
+    pub label: String,
+    pub started_at: i64,
+    pub entry_count: u64,
+    pub undone: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct UndoFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct UndoReport {
+    pub restored: u64,
+    pub failed: Vec<UndoFailure>,
+}
+
+/// Starts recording destructive commands under `label` and returns the operation id.
+#[tauri::command]
+pub async fn begin_operation(db: State<'_, Database>, active: State<'_, ActiveOperation>, label: String) -> Result<i64, AppError> {
+    let started_at = std::time::SystemTime::now()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64;
+
+    let conn = db.conn();
+    conn.execute(
+        "INSERT INTO operations (label, started_at) VALUES (?1, ?2)",
+        params![label, started_at],
+    )?;
+    let operation_id = conn.last_insert_rowid();
+    active.set(Some(operation_id));
+    Ok(operation_id)
+}
+
+/// Stops recording into the active operation.
+#[tauri::command]
+pub async fn end_operation(active: State<'_, ActiveOperation>) -> Result<(), AppError> {
+    active.set(None);
+    Ok(())
+}
+
+/// Lists recorded operations, most recent first.
+#[tauri::command]
+pub async fn list_operations(db: State<'_, Database>) -> Result<Vec<OperationSummary>, AppError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(
+        "SELECT o.id, o.label, o.started_at, o.undone, COUNT(e.id)
+         FROM operations o
+         LEFT JOIN operation_entries e ON e.operation_id = o.id
+         GROUP BY o.id
+         ORDER BY o.id DESC"
+    )?;
+
+    let operations = stmt.query_map([], |row| {
+        Ok(OperationSummary {
+            id: row.get(0)?,
+            label: row.get(1)?,
+            started_at: row.get(2)?,
+            undone: row.get(3)?,
+            entry_count: row.get(4)?,
+        })
+    })?;
+
+    Ok(operations.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Reverses every recorded move of `operation_id`, newest first: the file is moved back and its
+/// database row restored to the recorded before-state. Entries that fail (e.g. the file was
+/// deleted since) are reported and left in place so undo can be retried after fixing them.
+#[tauri::command]
+pub async fn undo_operation(db: State<'_, Database>, active: State<'_, ActiveOperation>, operation_id: i64) -> Result<UndoReport, AppError> {
+    if active.current() == Some(operation_id) {
+        active.set(None);
+    }
+
+    // Only the reads and the row restores hold the database lock: a move across drives copies
+    // the whole file.
+    let entries: Vec<(i64, String, String, String)> = {
+        let conn = db.conn();
+        let exists = conn.query_row("SELECT id FROM operations WHERE id = ?1", [operation_id], |row| row.get::<_, i64>(0))
+            .optional()?;
+        if exists.is_none() {
+            return Err(AppError::NotFound(format!("Operation not found: {}", operation_id)));
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT id, old_path, new_path, old_row FROM operation_entries
+             WHERE operation_id = ?1 AND undone = 0
+             ORDER BY id DESC"
+        )?;
+        let rows = stmt.query_map([operation_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    let mut report = UndoReport { restored: 0, failed: Vec::new() };
+    for (entry_id, old_path, new_path, old_row) in entries {
+        let result = async {
+            let before: VideoMetadata = serde_json::from_str(&old_row).map_err(|e| e.to_string())?;
+            if Path::new(&old_path).exists() {
+                return Err(format!("{} already exists", old_path));
+            }
+            if let Some(parent) = Path::new(&old_path).parent() {
+                std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            let (from, to) = (PathBuf::from(&new_path), PathBuf::from(&old_path));
+            tauri::async_runtime::spawn_blocking(move || crate::move_file(&from, &to))
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|e| e.to_string())?;
+
+            // Point the row back at the old path first so tag links follow it, then restore
+            // the remaining columns.
+            let conn = db.conn();
+            conn.execute(
+                "UPDATE videos SET full_path = ?1 WHERE full_path = ?2",
+                params![old_path, new_path],
+            ).map_err(|e| e.to_string())?;
+            database::upsert_video(&conn, &before).map_err(|e| e.to_string())?;
+            conn.execute("UPDATE operation_entries SET undone = 1 WHERE id = ?1", [entry_id])
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        }.await;
+
+        match result {
+            Ok(()) => report.restored += 1,
+            Err(error) => report.failed.push(UndoFailure { path: new_path, error }),
+        }
+    }
+
+    if report.failed.is_empty() {
+        db.conn().execute("UPDATE operations SET undone = 1 WHERE id = ?1", [operation_id])?;
+    }
+    Ok(report)
+}
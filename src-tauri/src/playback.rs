@@ -0,0 +1,157 @@
+// What the webview can play natively, and the cached per-video verdict derived from it.
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+use crate::VideoMetadata;
+
+/// Settings key holding the `PlaybackCapabilities`.
+const PLAYBACK_CAPABILITIES_KEY: &str = "playback_capabilities";
+
+/// Codec and container names (as reported by ffprobe) the webview plays without transcoding.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PlaybackCapabilities {
+    pub video_codecs: Vec<String>,
+    pub audio_codecs: Vec<String>,
+    /// Matched against each entry of ffprobe's comma-separated `format_name`.
+    pub containers: Vec<String>,
+}
+
+impl Default for PlaybackCapabilities {
+    fn default() -> Self {
+        // What Chromium- and WebKit-based webviews have in common.
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        PlaybackCapabilities {
+            video_codecs: names(&["h264", "vp8", "vp9", "av1"]),
+            audio_codecs: names(&["aac", "mp3", "opus", "vorbis", "flac"]),
+            containers: names(&["mp4", "mov", "webm"]),
+        }
+    }
+}
+
+pub fn playback_capabilities(conn: &Connection) -> PlaybackCapabilities {
+    database::get_setting(conn, PLAYBACK_CAPABILITIES_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+/// Which parts of a file the webview plays under some `PlaybackCapabilities`.
+struct StreamSupport {
+    container: bool,
+    video: bool,
+    /// Also true without an audio codec, which means the file has no audio.
+    audio: bool,
+}
+
+impl StreamSupport {
+    fn check(capabilities: &PlaybackCapabilities, container: &str, codec: &str, audio_codec: Option<&str>) -> Self {
+        let supported = |list: &[String], name: &str| list.iter().any(|entry| entry.eq_ignore_ascii_case(name));
+        StreamSupport {
+            container: container.split(',').any(|name| supported(&capabilities.containers, name.trim())),
+            video: supported(&capabilities.video_codecs, codec),
+            audio: audio_codec.is_none_or(|audio| supported(&capabilities.audio_codecs, audio)),
+        }
+    }
+
+    fn all(&self) -> bool {
+        self.container && self.video && self.audio
+    }
+}
+
+/// Whether `video` plays as-is under `capabilities`. `None` when the container or video codec
+/// isn't known (e.g. rows indexed before they were stored); a missing audio codec means the
+/// file has no audio and doesn't count against it.
+pub fn web_ready(video: &VideoMetadata, capabilities: &PlaybackCapabilities) -> Option<bool> {
+    let container = video.container.as_deref()?;
+    let codec = video.codec.as_deref()?;
+    Some(StreamSupport::check(capabilities, container, codec, video.audio_codec.as_deref()).all())
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WebPlayability {
+    /// Plays as-is under the configured capabilities.
+    pub playable: bool,
+    /// What stands in the way when not playable.
+    pub reason: Option<String>,
+    pub needs_video_transcode: bool,
+    pub needs_audio_transcode: bool,
+    /// Only the container is the problem and `remux_to_mp4` can fix it without re-encoding.
+    pub can_remux: bool,
+}
+
+/// Probes `path` and tells whether the webview can play it directly, and if not whether
+/// `remux_to_mp4` is enough or it needs `transcode_video_for_web`. Agrees with the cached
+/// `web_ready` of the same file.
+#[tauri::command]
+pub async fn is_web_playable(db: State<'_, Database>, path: String) -> Result<WebPlayability, AppError> {
+    let info = crate::extract_ffmpeg_metadata(std::path::Path::new(&path), crate::DEFAULT_PROBE_TIMEOUT).await?;
+    let capabilities = playback_capabilities(&db.conn());
+
+    let container = info.container.as_deref().unwrap_or("unknown");
+    let video_codec = info.codec.as_deref().unwrap_or("unknown");
+    let support = StreamSupport::check(&capabilities, container, video_codec, info.audio_codec.as_deref());
+
+    let mut problems = Vec::new();
+    if !support.video {
+        problems.push(format!("{} video isn't supported", video_codec));
+    }
+    if !support.audio {
+        problems.push(format!("{} audio isn't supported", info.audio_codec.as_deref().unwrap_or("unknown")));
+    }
+    if !support.container {
+        problems.push(format!("the {} container isn't supported", container));
+    }
+    let playable = support.all();
+    let can_remux = !playable
+        && support.video
+        && support.audio
+        && crate::remuxable_to_mp4(info.codec.as_deref(), info.audio_codec.as_deref(), info.audio_track_count);
+    Ok(WebPlayability {
+        playable,
+        reason: (!playable).then(|| problems.join(", ")),
+        needs_video_transcode: !support.video,
+        needs_audio_transcode: !support.audio,
+        can_remux,
+    })
+}
+
+fn recompute(conn: &mut Connection) -> Result<u64, AppError> {
+    let capabilities = playback_capabilities(conn);
+    let videos = database::query_videos(conn, "", [])?;
+
+    let tx = conn.transaction()?;
+    for video in &videos {
+        tx.execute(
+            "UPDATE videos SET web_ready = ?1 WHERE full_path = ?2",
+            rusqlite::params![web_ready(video, &capabilities), video.full_path],
+        )?;
+    }
+    tx.commit()?;
+
+    Ok(videos.len() as u64)
+}
+
+#[tauri::command]
+pub async fn get_playback_capabilities(db: State<'_, Database>) -> Result<PlaybackCapabilities, AppError> {
+    Ok(playback_capabilities(&db.conn()))
+}
+
+/// Stores new capabilities and recomputes `web_ready` for every video to match.
+#[tauri::command]
+pub async fn set_playback_capabilities(db: State<'_, Database>, capabilities: PlaybackCapabilities) -> Result<u64, AppError> {
+    let mut conn = db.conn();
+    database::set_setting(&conn, PLAYBACK_CAPABILITIES_KEY, &capabilities)?;
+    recompute(&mut conn)
+}
+
+/// Recomputes `web_ready` for every stored video from its stored codec and container fields
+/// (no probing). Returns the number of rows updated.
+#[tauri::command]
+pub async fn recompute_web_readiness(db: State<'_, Database>) -> Result<u64, AppError> {
+    recompute(&mut db.conn())
+}
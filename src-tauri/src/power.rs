@@ -0,0 +1,186 @@
+// Power source detection, used to go easy on the battery during long crawls.
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+
+/// Settings key holding the `BatteryPolicy`.
+pub const BATTERY_POLICY_KEY: &str = "battery_policy";
+
+/// How often a long-running loop re-reads the power source.
+const POWER_RECHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long a paused loop waits before checking whether AC power is back.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum PowerSource {
+    Ac,
+    Battery,
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PowerState {
+    pub source: PowerSource,
+    pub battery_percent: Option<u8>,
+}
+
+/// What crawls and batch thumbnailing do while the machine runs on battery.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum BatteryPolicy {
+    /// Run at full speed regardless of the power source.
+    Ignore,
+    /// Idle after every file for as long as the file took, halving the work rate.
+    #[default]
+    Throttle,
+    /// Stop processing until AC power is back.
+    Pause,
+}
+
+#[cfg(windows)]
+fn read_power_state() -> PowerState {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    // SAFETY: SYSTEM_POWER_STATUS is plain data and GetSystemPowerStatus only writes into it.
+    let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return PowerState { source: PowerSource::Unknown, battery_percent: None };
+    }
+
+    let source = match status.ACLineStatus {
+        0 => PowerSource::Battery,
+        1 => PowerSource::Ac,
+        _ => PowerSource::Unknown,
+    };
+    // 255 means unknown; flag 128 means the system has no battery at all.
+    let battery_percent = (status.BatteryLifePercent <= 100 && status.BatteryFlag & 128 == 0)
+        .then_some(status.BatteryLifePercent);
+    PowerState { source, battery_percent }
+}
+
+#[cfg(target_os = "linux")]
+fn read_power_state() -> PowerState {
+    let read = |path: std::path::PathBuf| std::fs::read_to_string(path).map(|s| s.trim().to_string()).ok();
+
+    let mut on_ac = None;
+    let mut discharging = false;
+    let mut battery_percent = None;
+    for entry in std::fs::read_dir("/sys/class/power_supply").into_iter().flatten().flatten() {
+        let supply = entry.path();
+        match read(supply.join("type")).as_deref() {
+            Some("Mains") | Some("USB") => {
+                if read(supply.join("online")).as_deref() == Some("1") {
+                    on_ac = Some(true);
+                } else if on_ac.is_none() {
+                    on_ac = Some(false);
+                }
+            }
+            Some("Battery") => {
+                discharging |= read(supply.join("status")).as_deref() == Some("Discharging");
+                battery_percent = battery_percent.or_else(|| read(supply.join("capacity"))?.parse().ok());
+            }
+            _ => {}
+        }
+    }
+
+    let source = match (on_ac, discharging) {
+        (Some(true), _) => PowerSource::Ac,
+        (_, true) | (Some(false), _) => PowerSource::Battery,
+        // No supplies reported at all, most likely a desktop.
+        _ if battery_percent.is_none() => PowerSource::Ac,
+        _ => PowerSource::Unknown,
+    };
+    PowerState { source, battery_percent }
+}
+
+#[cfg(target_os = "macos")]
+fn read_power_state() -> PowerState {
+    // First line is e.g. "Now drawing from 'Battery Power'", followed by " -InternalBattery-0 ... 87%; ..."
+    let Ok(output) = std::process::Command::new("pmset").args(["-g", "batt"]).output() else {
+        return PowerState { source: PowerSource::Unknown, battery_percent: None };
+    };
+    let text = String::from_utf8_lossy(&output.stdout);
+    let source = if text.contains("'AC Power'") {
+        PowerSource::Ac
+    } else if text.contains("'Battery Power'") {
+        PowerSource::Battery
+    } else {
+        PowerSource::Unknown
+    };
+    let battery_percent = text.split_whitespace()
+        .find_map(|word| word.trim_end_matches(';').strip_suffix('%')?.parse().ok());
+    PowerState { source, battery_percent }
+}
+
+#[cfg(not(any(windows, target_os = "linux", target_os = "macos")))]
+fn read_power_state() -> PowerState {
+    PowerState { source: PowerSource::Unknown, battery_percent: None }
+}
+
+pub fn battery_policy(app: &AppHandle) -> BatteryPolicy {
+    database::get_setting(&app.state::<Database>().conn(), BATTERY_POLICY_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+/// Applies the battery policy between units of work in a long-running loop.
+pub struct BatteryThrottle {
+    policy: BatteryPolicy,
+    on_battery: bool,
+    checked_at: Option<Instant>,
+}
+
+impl BatteryThrottle {
+    pub fn new(policy: BatteryPolicy) -> Self {
+        BatteryThrottle { policy, on_battery: false, checked_at: None }
+    }
+
+    fn on_battery(&mut self, force: bool) -> bool {
+        let stale = self.checked_at.is_none_or(|at| at.elapsed() >= POWER_RECHECK_INTERVAL);
+        if force || stale {
+            self.on_battery = read_power_state().source == PowerSource::Battery;
+            self.checked_at = Some(Instant::now());
+        }
+        self.on_battery
+    }
+
+    /// Call after each unit of work, passing how long that work took. Returns early once
+    /// `stopped` is true, so a paused loop can still be cancelled.
+    pub async fn pace(&mut self, work: Duration, stopped: impl Fn() -> bool) {
+        match self.policy {
+            BatteryPolicy::Ignore => {}
+            BatteryPolicy::Throttle => {
+                if self.on_battery(false) && !stopped() {
+                    tokio::time::sleep(work).await;
+                }
+            }
+            BatteryPolicy::Pause => {
+                let mut force = false;
+                while !stopped() && self.on_battery(force) {
+                    tokio::time::sleep(PAUSE_POLL_INTERVAL).await;
+                    force = true;
+                }
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_power_state() -> Result<PowerState, AppError> {
+    Ok(read_power_state())
+}
+
+#[tauri::command]
+pub async fn get_battery_policy(app: AppHandle) -> Result<BatteryPolicy, AppError> {
+    Ok(battery_policy(&app))
+}
+
+#[tauri::command]
+pub async fn set_battery_policy(db: State<'_, Database>, policy: BatteryPolicy) -> Result<(), AppError> {
+    database::set_setting(&db.conn(), BATTERY_POLICY_KEY, &policy)
+}
@@ -0,0 +1,106 @@
+// Pull-based crawls: the frontend starts a crawl in the background and fetches the extracted
+// videos in batches at its own pace, instead of receiving them all at once.
+//
+// Results go through a bounded channel, so a crawl nobody polls pauses once `BUFFERED_RESULTS`
+// videos are waiting rather than piling them up in memory.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, State};
+use tokio::sync::mpsc::{self, error::TryRecvError};
+
+use crate::{CrawlOptions, VideoMetadata};
+use crate::error::AppError;
+
+/// Extracted videos held for a crawl before it waits for the next poll.
+const BUFFERED_RESULTS: usize = 256;
+
+struct PulledCrawl {
+    results: mpsc::Receiver<VideoMetadata>,
+    /// Set by the crawl task if the crawl fails.
+    error: Arc<Mutex<Option<String>>>,
+}
+
+/// Crawls started with `start_crawl` that have not been fully polled yet, by handle.
+#[derive(Default)]
+pub struct PulledCrawls {
+    crawls: Mutex<HashMap<u64, PulledCrawl>>,
+    next_handle: Mutex<u64>,
+}
+
+impl PulledCrawls {
+    fn crawls(&self) -> std::sync::MutexGuard<'_, HashMap<u64, PulledCrawl>> {
+        self.crawls.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CrawlBatch {
+    pub videos: Vec<VideoMetadata>,
+    /// The crawl has finished and every result has been returned; the handle is now released.
+    pub done: bool,
+    /// Why the crawl stopped early, reported with `done`.
+    pub error: Option<String>,
+}
+
+/// Starts crawling `root` in the background and returns a handle for `poll_crawl_results`.
+/// "crawl-progress" events are sent as for `crawl_directory`.
+#[tauri::command]
+pub async fn start_crawl(app: AppHandle, crawls: State<'_, PulledCrawls>, root: String, options: Option<CrawlOptions>) -> Result<u64, AppError> {
+    let (sender, results) = mpsc::channel(BUFFERED_RESULTS);
+    let error = Arc::new(Mutex::new(None));
+
+    let handle = {
+        let mut next_handle = crawls.next_handle.lock().unwrap_or_else(|e| e.into_inner());
+        *next_handle += 1;
+        *next_handle
+    };
+    crawls.crawls().insert(handle, PulledCrawl { results, error: error.clone() });
+
+    tauri::async_runtime::spawn(async move {
+        let options = options.unwrap_or_default();
+        if let Err(e) = crate::crawl(&app, &[&root], &options, Some(&sender), None).await {
+            *error.lock().unwrap_or_else(|e| e.into_inner()) = Some(e.to_string());
+        }
+    });
+    Ok(handle)
+}
+
+/// Returns up to `max` videos extracted since the last poll, without waiting for more. Once
+/// `done` is returned the handle is no longer valid.
+#[tauri::command]
+pub async fn poll_crawl_results(crawls: State<'_, PulledCrawls>, handle: u64, max: usize) -> Result<CrawlBatch, AppError> {
+    let mut crawls = crawls.crawls();
+    let crawl = crawls.get_mut(&handle).ok_or_else(|| AppError::NotFound(format!("Crawl not found: {}", handle)))?;
+
+    let mut videos = Vec::new();
+    let mut done = false;
+    while videos.len() < max {
+        match crawl.results.try_recv() {
+            Ok(video) => videos.push(video),
+            Err(TryRecvError::Empty) => break,
+            Err(TryRecvError::Disconnected) => {
+                done = true;
+                break;
+            }
+        }
+    }
+
+    let error = if done {
+        let crawl = crawls.remove(&handle).expect("crawl was just polled");
+        let mut error = crawl.error.lock().unwrap_or_else(|e| e.into_inner());
+        error.take()
+    } else {
+        None
+    };
+    Ok(CrawlBatch { videos, done, error })
+}
+
+/// Stops a crawl started with `start_crawl` and releases its handle. Results not yet polled are
+/// discarded.
+#[tauri::command]
+pub async fn release_crawl(crawls: State<'_, PulledCrawls>, handle: u64) -> Result<(), AppError> {
+    // Dropping the receiver makes the crawl stop at its next result.
+    crawls.crawls().remove(&handle).map(|_| ()).ok_or_else(|| AppError::NotFound(format!("Crawl not found: {}", handle)))
+}
@@ -0,0 +1,112 @@
+// Registered crawl roots: directories the library is built from, remembered across launches.
+
+use serde::Serialize;
+use std::path::Path;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+use crate::{CrawlOptions, IndexSummary};
+
+/// Settings key holding the registered roots as a list of normalized paths.
+const CRAWL_ROOTS_KEY: &str = "crawl_roots";
+
+/// Settings key for whether registered roots are re-indexed when the app starts.
+const INDEX_ROOTS_ON_STARTUP_KEY: &str = "index_roots_on_startup";
+
+/// Result for one root, sent as a "root-indexed" event and returned by `index_registered_roots`.
+#[derive(Debug, Serialize, Clone)]
+pub struct RootIndexResult {
+    pub root: String,
+    pub summary: Option<IndexSummary>,
+    pub error: Option<String>,
+}
+
+fn registered_roots(db: &Database) -> Result<Vec<String>, AppError> {
+    Ok(database::get_setting(&db.conn(), CRAWL_ROOTS_KEY)?.unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn get_registered_roots(db: State<'_, Database>) -> Result<Vec<String>, AppError> {
+    registered_roots(&db)
+}
+
+/// Registers `path` as a crawl root. Adding a root that is already registered is a no-op.
+/// Returns the updated list.
+#[tauri::command]
+pub async fn add_root(db: State<'_, Database>, path: String) -> Result<Vec<String>, AppError> {
+    if !Path::new(&path).is_dir() {
+        return Err(AppError::InvalidInput(format!("{} is not a directory", path)));
+    }
+
+    let path = database::normalize_path(&path);
+    let mut roots = registered_roots(&db)?;
+    if !roots.contains(&path) {
+        roots.push(path);
+        roots.sort();
+        database::set_setting(&db.conn(), CRAWL_ROOTS_KEY, &roots)?;
+    }
+    Ok(roots)
+}
+
+/// Unregisters a crawl root. The videos already indexed from it stay in the library.
+/// Returns the updated list.
+#[tauri::command]
+pub async fn remove_root(db: State<'_, Database>, path: String) -> Result<Vec<String>, AppError> {
+    let path = database::normalize_path(&path);
+    let mut roots = registered_roots(&db)?;
+    roots.retain(|root| *root != path);
+    database::set_setting(&db.conn(), CRAWL_ROOTS_KEY, &roots)?;
+    Ok(roots)
+}
+
+#[tauri::command]
+pub async fn get_index_roots_on_startup(db: State<'_, Database>) -> Result<bool, AppError> {
+    Ok(database::get_setting(&db.conn(), INDEX_ROOTS_ON_STARTUP_KEY)?.unwrap_or(false))
+}
+
+#[tauri::command]
+pub async fn set_index_roots_on_startup(db: State<'_, Database>, enabled: bool) -> Result<(), AppError> {
+    database::set_setting(&db.conn(), INDEX_ROOTS_ON_STARTUP_KEY, &enabled)
+}
+
+async fn index_roots(app: &AppHandle) -> Result<Vec<RootIndexResult>, AppError> {
+    let roots = registered_roots(&app.state::<Database>())?;
+    let mut results = Vec::with_capacity(roots.len());
+    for root in roots {
+        let result = match crate::index_changed_files(app, &root, &CrawlOptions::default()).await {
+            Ok(report) => RootIndexResult { root, summary: Some(report.summary), error: None },
+            Err(e) => RootIndexResult { root, summary: None, error: Some(e.to_string()) },
+        };
+        let _ = app.emit("root-indexed", result.clone());
+        results.push(result);
+    }
+    Ok(results)
+}
+
+/// Incrementally indexes every registered root in turn (only new or changed files are probed).
+/// Progress is reported through "crawl-progress" events for the root being indexed and a
+/// "root-indexed" event after each root.
+#[tauri::command]
+pub async fn index_registered_roots(app: AppHandle) -> Result<Vec<RootIndexResult>, AppError> {
+    index_roots(&app).await
+}
+
+/// Starts `index_registered_roots` in the background if enabled in the settings. Called from
+/// the setup hook, so failures are only logged.
+pub fn index_on_startup(app: &AppHandle) {
+    let enabled = database::get_setting(&app.state::<Database>().conn(), INDEX_ROOTS_ON_STARTUP_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or(false);
+    if !enabled {
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = index_roots(&app).await {
+            log::error!("Error indexing registered roots: {}", e);
+        }
+    });
+}
@@ -0,0 +1,142 @@
+// Season/episode numbering parsed from file names, e.g. `Show.S01E05.mkv` or `Show 1x05.mkv`.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tauri::State;
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+
+/// Season and episode numbers of one file. Multi-episode files (`S01E05E06`, `S01E05-E06`)
+/// cover `episode..=last_episode`.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub struct EpisodeNumber {
+    pub season: u32,
+    pub episode: u32,
+    pub last_episode: u32,
+}
+
+/// Reads the run of ASCII digits at the start of `text`: its value and length.
+fn leading_number(text: &[u8]) -> Option<(u32, usize)> {
+    let len = text.iter().take_while(|b| b.is_ascii_digit()).count();
+    if len == 0 || len > 4 {
+        return None;
+    }
+    let value = std::str::from_utf8(&text[..len]).ok()?.parse().ok()?;
+    Some((value, len))
+}
+
+/// Parses the first `SxxEyy` or `NxNN` marker of a file name (case-insensitive). The marker
+/// must not be glued to letters or digits before it, so `Marsexpress` or `1920x1080` don't count.
+pub fn parse_episode(file_name: &str) -> Option<EpisodeNumber> {
+    let name = file_name.to_ascii_lowercase();
+    let bytes = name.as_bytes();
+
+    for start in 0..bytes.len() {
+        if start > 0 && bytes[start - 1].is_ascii_alphanumeric() {
+            continue;
+        }
+
+        // S01E05, with optional further episodes: S01E05E06, S01E05-E06, S01E05-06.
+        if bytes[start] == b's' {
+            let Some((season, season_len)) = leading_number(&bytes[start + 1..]) else { continue };
+            let mut at = start + 1 + season_len;
+            if bytes.get(at) != Some(&b'e') {
+                continue;
+            }
+            let Some((episode, episode_len)) = leading_number(&bytes[at + 1..]) else { continue };
+            at += 1 + episode_len;
+
+            let mut last_episode = episode;
+            loop {
+                let skip = match (bytes.get(at), bytes.get(at + 1)) {
+                    (Some(b'e'), _) => 1,
+                    (Some(b'-'), Some(b'e')) => 2,
+                    (Some(b'-'), Some(b'0'..=b'9')) => 1,
+                    _ => break,
+                };
+                let Some((next, next_len)) = leading_number(&bytes[at + skip..]) else { break };
+                if next <= last_episode {
+                    break;
+                }
+                last_episode = next;
+                at += skip + next_len;
+            }
+            return Some(EpisodeNumber { season, episode, last_episode });
+        }
+
+        // 1x05. Resolutions like 1280x720 are excluded by the digit limits.
+        if let Some((season, season_len)) = leading_number(&bytes[start..]) {
+            let at = start + season_len;
+            if season_len <= 2 && bytes.get(at) == Some(&b'x') {
+                if let Some((episode, episode_len)) = leading_number(&bytes[at + 1..]) {
+                    let end = at + 1 + episode_len;
+                    if episode_len <= 3 && !bytes.get(end).is_some_and(u8::is_ascii_alphanumeric) {
+                        return Some(EpisodeNumber { season, episode, last_episode: episode });
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SeasonCompleteness {
+    pub season: u32,
+    /// Highest episode number present.
+    pub last_episode: u32,
+    /// Episodes from 1 to `last_episode` without a file.
+    pub missing: Vec<u32>,
+    /// Episodes with more than one file.
+    pub duplicated: Vec<u32>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SeriesCompleteness {
+    pub folder_name: String,
+    pub seasons: Vec<SeasonCompleteness>,
+    /// Files of the folder without a recognizable episode number.
+    pub unparsed: Vec<String>,
+}
+
+/// Checks the episodes of a folder for gaps, season by season, from the numbers in the file
+/// names. Episodes after the highest one present can't be detected as missing. Nothing is
+/// changed.
+#[tauri::command]
+pub async fn check_series_completeness(db: State<'_, Database>, folder_name: String) -> Result<SeriesCompleteness, AppError> {
+    let videos = database::query_videos(
+        &db.conn(),
+        "WHERE folder_name = ?1 ORDER BY file_name",
+        [&folder_name],
+    )?;
+
+    // Season -> episode -> number of files covering it.
+    let mut seasons: BTreeMap<u32, BTreeMap<u32, u32>> = BTreeMap::new();
+    let mut unparsed = Vec::new();
+    for video in videos {
+        match parse_episode(&video.file_name) {
+            Some(number) => {
+                let episodes = seasons.entry(number.season).or_default();
+                for episode in number.episode..=number.last_episode {
+                    *episodes.entry(episode).or_default() += 1;
+                }
+            }
+            None => unparsed.push(video.file_name),
+        }
+    }
+
+    let seasons = seasons.into_iter()
+        .map(|(season, episodes)| {
+            let last_episode = episodes.keys().next_back().copied().unwrap_or(0);
+            SeasonCompleteness {
+                season,
+                last_episode,
+                missing: (1..=last_episode).filter(|episode| !episodes.contains_key(episode)).collect(),
+                duplicated: episodes.iter().filter(|(_, count)| **count > 1).map(|(episode, _)| *episode).collect(),
+            }
+        })
+        .collect();
+
+    Ok(SeriesCompleteness { folder_name, seasons, unparsed })
+}
@@ -0,0 +1,187 @@
+// Continuous recordings that the camera split into several files, and stitching them back.
+//
+// Two naming schemes are recognized:
+// - GoPro chapters: `GH01xxxx`, `GH02xxxx`, ... (also `GX`), and the older `GOPRxxxx` followed
+//   by `GP01xxxx`, `GP02xxxx`, ...; the parts share the four-digit file number.
+// - Dashcam segments named by their start time, e.g. `20231010_123000_F.MP4`; consecutive files
+//   with the same name around the timestamp form one recording when each starts where the
+//   previous one ended.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tauri::{AppHandle, State};
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+use crate::VideoMetadata;
+
+/// Largest gap or overlap (seconds) between the end of one dashcam segment and the start of
+/// the next for them to count as one recording.
+const SEGMENT_GAP_TOLERANCE: f64 = 5.0;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SplitRecording {
+    /// Full path of the first part; pass it to `concat_split_recording`.
+    pub group_id: String,
+    /// In recording order.
+    pub parts: Vec<VideoMetadata>,
+    /// Sum of the known part durations, in seconds.
+    pub total_duration: f64,
+}
+
+/// For a GoPro file stem (uppercased), the name shared by all chapters of the recording and this
+/// file's chapter number.
+fn chaptered_part(stem: &str) -> Option<(String, u32)> {
+    if stem.len() != 8 || !stem.is_ascii() {
+        return None;
+    }
+    let (prefix, rest) = stem.split_at(2);
+    let (chapter, number) = rest.split_at(2);
+    if !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    match (prefix, chapter) {
+        ("GO", "PR") => Some((format!("GOPR{}", number), 0)),
+        ("GH" | "GX" | "GP", _) if chapter.bytes().all(|b| b.is_ascii_digit()) => {
+            // Older cameras call the first chapter GOPR and the following ones GP.
+            let prefix = if prefix == "GP" { "GOPR" } else { prefix };
+            Some((format!("{}{}", prefix, number), chapter.parse().ok()?))
+        }
+        _ => None,
+    }
+}
+
+/// For a file stem containing a `YYYYMMDD[_-T]HHMMSS` timestamp, the stem with the timestamp
+/// replaced by `*` and the timestamp in seconds (local time, only used for differences).
+fn timestamped_part(stem: &str) -> Option<(String, i64)> {
+    let bytes = stem.as_bytes();
+    let digits = |from: usize, len: usize| -> Option<i64> {
+        if !bytes.get(from..from + len)?.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        stem[from..from + len].parse().ok()
+    };
+
+    for start in 0..bytes.len() {
+        if start > 0 && bytes[start - 1].is_ascii_digit() {
+            continue;
+        }
+        let Some(date) = digits(start, 8) else { continue };
+        let time_start = if matches!(bytes.get(start + 8), Some(b'_' | b'-' | b'T')) { start + 9 } else { start + 8 };
+        let Some(time) = digits(time_start, 6) else { continue };
+
+        let (month, day) = ((date / 100 % 100) as u32, (date % 100) as u32);
+        let (hour, minute, second) = (time / 10000, time / 100 % 100, time % 100);
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 59 {
+            continue;
+        }
+        let seconds = crate::days_from_civil(date / 10000, month, day) * 86_400 + hour * 3600 + minute * 60 + second;
+        let key = format!("{}*{}", &stem[..start], &stem[time_start + 6..]);
+        return Some((key, seconds));
+    }
+    None
+}
+
+fn split_recording(parts: Vec<VideoMetadata>) -> SplitRecording {
+    SplitRecording {
+        group_id: parts[0].full_path.clone(),
+        total_duration: parts.iter().filter_map(|part| part.duration).sum(),
+        parts,
+    }
+}
+
+fn detect(videos: Vec<VideoMetadata>) -> Vec<SplitRecording> {
+    // Parts are only grouped within one directory and extension, keyed by the shared name.
+    let mut chaptered: BTreeMap<(String, String, String), Vec<(u32, VideoMetadata)>> = BTreeMap::new();
+    let mut timestamped: BTreeMap<(String, String, String), Vec<(i64, VideoMetadata)>> = BTreeMap::new();
+
+    for video in videos {
+        let path = Path::new(&video.full_path);
+        let directory = path.parent().map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
+        let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        let Some(stem) = path.file_stem().map(|s| s.to_string_lossy().to_uppercase()) else { continue };
+
+        if let Some((name, chapter)) = chaptered_part(&stem) {
+            chaptered.entry((directory, extension, name)).or_default().push((chapter, video));
+        } else if let Some((name, start)) = timestamped_part(&stem) {
+            timestamped.entry((directory, extension, name)).or_default().push((start, video));
+        }
+    }
+
+    let mut recordings = Vec::new();
+    for mut parts in chaptered.into_values() {
+        if parts.len() > 1 {
+            parts.sort_by_key(|(chapter, _)| *chapter);
+            recordings.push(split_recording(parts.into_iter().map(|(_, video)| video).collect()));
+        }
+    }
+
+    for mut segments in timestamped.into_values() {
+        segments.sort_by_key(|(start, _)| *start);
+        let mut chain: Vec<VideoMetadata> = Vec::new();
+        let mut chain_end: Option<f64> = None;
+        for (start, video) in segments {
+            let continues = chain_end.is_some_and(|end| (start as f64 - end).abs() <= SEGMENT_GAP_TOLERANCE);
+            if !continues {
+                if chain.len() > 1 {
+                    recordings.push(split_recording(std::mem::take(&mut chain)));
+                }
+                chain.clear();
+            }
+            // Without a duration the next segment cannot be matched up, so the chain ends here.
+            chain_end = video.duration.map(|duration| start as f64 + duration);
+            chain.push(video);
+        }
+        if chain.len() > 1 {
+            recordings.push(split_recording(chain));
+        }
+    }
+
+    recordings.sort_by(|a, b| a.group_id.cmp(&b.group_id));
+    recordings
+}
+
+/// Finds recordings the camera split into several files (GoPro chapters, dashcam segments) and
+/// returns each with its parts in order.
+#[tauri::command]
+pub async fn detect_split_recordings(db: State<'_, Database>) -> Result<Vec<SplitRecording>, AppError> {
+    Ok(detect(database::query_videos(&db.conn(), "", [])?))
+}
+
+/// Joins the parts of the split recording `group_id` (from `detect_split_recordings`) into
+/// `output_path` without re-encoding. ffmpeg output is streamed as "ffmpeg-log" events. The
+/// result is not indexed.
+#[tauri::command]
+pub async fn concat_split_recording(app: AppHandle, db: State<'_, Database>, group_id: String, output_path: String) -> Result<String, AppError> {
+    if Path::new(&output_path).exists() {
+        return Err(AppError::InvalidInput(format!("{} already exists", output_path)));
+    }
+    let recording = detect(database::query_videos(&db.conn(), "", [])?)
+        .into_iter()
+        .find(|recording| recording.group_id == group_id)
+        .ok_or_else(|| AppError::NotFound(format!("Split recording not found: {}", group_id)))?;
+
+    // The concat demuxer reads the parts from a list file; quotes in paths are escaped as '\''.
+    let list = recording.parts.iter()
+        .map(|part| format!("file '{}'\n", part.full_path.replace('\'', "'\\''")))
+        .collect::<String>();
+    let list_path = std::env::temp_dir().join(format!(
+        "shadowcrawler_concat_{}.txt",
+        Path::new(&group_id).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default(),
+    ));
+    std::fs::write(&list_path, list)?;
+
+    let list_arg = list_path.to_string_lossy().to_string();
+    let result = crate::run_ffmpeg_logged(
+        &app,
+        &group_id,
+        &["-f", "concat", "-safe", "0", "-i", &list_arg, "-c", "copy", &output_path],
+        "Concatenation failed",
+        |_| (),
+        None,
+    ).await;
+    let _ = std::fs::remove_file(&list_path);
+    result.map(|()| output_path)
+}
@@ -0,0 +1,125 @@
+// Storyboard sprite sheets: evenly spaced frames of a video tiled into one JPEG, so the seek bar
+// can show a preview of any position by offsetting into the sheet.
+
+use serde::Serialize;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+use tokio::process::Command;
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+use crate::{ffmpeg_failure, ffmpeg_paths, full_decode_timeout, output_with_timeout, thumbnail_cache};
+
+/// Each tile covers at least this many seconds, so short videos get fewer tiles.
+const MIN_SECONDS_PER_TILE: f64 = 1.0;
+
+/// From this spacing on only keyframes are decoded, which is much faster on long recordings.
+/// A tile then shows the first keyframe at or after its timestamp.
+const KEYFRAME_ONLY_SECONDS_PER_TILE: f64 = 30.0;
+
+/// Aspect ratio assumed for tiles when the video's size is unknown.
+const DEFAULT_TILE_ASPECT: f64 = 16.0 / 9.0;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Storyboard {
+    pub sheet_path: String,
+    /// The grid actually used, smaller than requested for short videos. Tiles fill it row by
+    /// row from the top left; trailing cells of the last row may be empty.
+    pub columns: u32,
+    pub rows: u32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    /// Position in seconds of each tile, in tile order.
+    pub timestamps: Vec<f64>,
+}
+
+/// Duration and displayed size, from the library when the file is indexed and from ffprobe
+/// otherwise.
+async fn video_shape(app: &AppHandle, video_path: &str) -> Result<(f64, Option<(u32, u32)>), AppError> {
+    let (duration, width, height) = match crate::stored_video(app, video_path)? {
+        Some(video) if video.duration.is_some() => (
+            video.duration,
+            video.display_width.or(video.width),
+            video.display_height.or(video.height),
+        ),
+        _ => {
+            let info = crate::extract_ffmpeg_metadata(Path::new(video_path), crate::DEFAULT_PROBE_TIMEOUT).await?;
+            (info.duration, info.display_width.or(info.width), info.display_height.or(info.height))
+        }
+    };
+    let duration = duration
+        .filter(|d| d.is_finite() && *d > 0.0)
+        .ok_or_else(|| AppError::Other("Could not determine the video duration".to_string()))?;
+    Ok((duration, width.zip(height).filter(|(w, h)| *w > 0 && *h > 0)))
+}
+
+/// Generates (or reuses) a `columns` x `rows` sprite sheet of `video_path` with tiles
+/// `frame_width` pixels wide, one per equal slice of the duration. Sheets live in the thumbnail
+/// cache.
+#[tauri::command]
+pub async fn generate_storyboard(app: AppHandle, video_path: String, columns: u32, rows: u32, frame_width: u32) -> Result<Storyboard, AppError> {
+    if columns == 0 || rows == 0 {
+        return Err(AppError::InvalidInput("The storyboard needs at least one column and one row".to_string()));
+    }
+    if frame_width < 16 {
+        return Err(AppError::InvalidInput("frame_width must be at least 16 pixels".to_string()));
+    }
+
+    let (duration, size) = video_shape(&app, &video_path).await?;
+    let fitting = ((duration / MIN_SECONDS_PER_TILE).floor() as u32).max(1);
+    let count = (columns * rows).min(fitting);
+    let columns = columns.min(count);
+    let rows = count.div_ceil(columns);
+
+    let aspect = size.map(|(w, h)| w as f64 / h as f64).unwrap_or(DEFAULT_TILE_ASPECT);
+    let tile_height = (((frame_width as f64 / aspect) / 2.0).round() as u32 * 2).max(2);
+
+    // Each tile shows the middle of its slice.
+    let interval = duration / count as f64;
+    let timestamps: Vec<f64> = (0..count).map(|i| interval * (i as f64 + 0.5)).collect();
+
+    let path = Path::new(&video_path);
+    let sheet_path = crate::thumbnail_path_for(path, &format!("_storyboard_{}x{}_{}.jpg", columns, rows, frame_width))?;
+    let normalized = database::normalize_path(&sheet_path.to_string_lossy());
+    let storyboard = Storyboard {
+        sheet_path: normalized.clone(),
+        columns,
+        rows,
+        tile_width: frame_width,
+        tile_height,
+        timestamps,
+    };
+    if sheet_path.exists() {
+        thumbnail_cache::touch(&app.state::<Database>().conn(), &normalized)?;
+        return Ok(storyboard);
+    }
+
+    // `selected_n` counts the frames picked so far, so each pick waits for the next timestamp.
+    let filter = format!(
+        "select='gte(t,{:.3}+{:.3}*selected_n)',scale={}:{},tile={}x{}:nb_frames={}",
+        interval / 2.0, interval, frame_width, tile_height, columns, rows, count,
+    );
+    let mut command = Command::new(ffmpeg_paths::ffmpeg());
+    if interval >= KEYFRAME_ONLY_SECONDS_PER_TILE {
+        command.args(["-skip_frame", "nokey"]);
+    }
+    command
+        .arg("-i")
+        .arg(path)
+        .args(["-an", "-vf", &filter, "-frames:v", "1", "-q:v", "4", "-y"])
+        .arg(&sheet_path);
+    // A sheet left behind by a failed or killed run would be served as finished next time.
+    let output = output_with_timeout(&mut command, "ffmpeg", full_decode_timeout(Some(duration)))
+        .await
+        .inspect_err(|_| {
+            let _ = std::fs::remove_file(&sheet_path);
+        })?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&sheet_path);
+        return Err(AppError::FfmpegFailed(ffmpeg_failure("Storyboard generation failed", &String::from_utf8_lossy(&output.stderr))));
+    }
+
+    thumbnail_cache::touch(&app.state::<Database>().conn(), &normalized)?;
+    thumbnail_cache::enforce_limit_in_background(&app);
+    Ok(storyboard)
+}
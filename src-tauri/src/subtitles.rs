@@ -0,0 +1,72 @@
+// Embedded subtitle tracks: listed on `VideoMetadata::subtitle_tracks` and extracted to
+// SRT/WebVTT files for editing.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::error::AppError;
+use crate::{ffmpeg_failure, ffmpeg_paths, full_decode_timeout, output_with_timeout};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SubtitleTrack {
+    /// Stream index in the file as reported by ffprobe, the `track_index` of `extract_subtitles`.
+    pub index: u32,
+    /// Codec as reported by ffprobe, e.g. "subrip", "mov_text" or "hdmv_pgs_subtitle".
+    pub codec: Option<String>,
+    /// Language tag of the stream, e.g. "eng", if set.
+    pub language: Option<String>,
+}
+
+/// Subtitle codecs that store pictures rather than text, so they can't be written as SRT/WebVTT.
+const BITMAP_SUBTITLE_CODECS: &[&str] = &["hdmv_pgs_subtitle", "dvd_subtitle", "dvb_subtitle", "xsub"];
+
+/// The subtitle tracks among ffprobe's `streams` array.
+pub fn subtitle_tracks(streams: &[serde_json::Value]) -> Vec<SubtitleTrack> {
+    streams.iter()
+        .filter(|stream| stream["codec_type"] == "subtitle")
+        .filter_map(|stream| Some(SubtitleTrack {
+            index: stream["index"].as_u64()? as u32,
+            codec: stream["codec_name"].as_str().map(str::to_string),
+            language: stream["tags"]["language"].as_str()
+                .map(str::trim)
+                .filter(|language| !language.is_empty() && *language != "und")
+                .map(str::to_string),
+        }))
+        .collect()
+}
+
+/// Writes subtitle track `track_index` (see `SubtitleTrack::index`) of `video_path` to
+/// `out_path`. The format follows the extension: `.srt` for SubRip, `.vtt` for WebVTT. Tracks
+/// stored as pictures (Blu-ray and DVD subtitles) can't be converted.
+#[tauri::command]
+pub async fn extract_subtitles(video_path: String, track_index: u32, out_path: String) -> Result<String, AppError> {
+    let encoder = match Path::new(&out_path).extension().map(|e| e.to_string_lossy().to_lowercase()).as_deref() {
+        Some("srt") => "srt",
+        Some("vtt") => "webvtt",
+        _ => return Err(AppError::InvalidInput(format!("{} must end in .srt or .vtt", out_path))),
+    };
+
+    let info = crate::extract_ffmpeg_metadata(Path::new(&video_path), crate::DEFAULT_PROBE_TIMEOUT).await?;
+    let track = info.subtitle_tracks.iter()
+        .find(|track| track.index == track_index)
+        .ok_or_else(|| AppError::NotFound(format!("No subtitle track {} in {}", track_index, video_path)))?;
+    if track.codec.as_deref().is_some_and(|codec| BITMAP_SUBTITLE_CODECS.contains(&codec)) {
+        return Err(AppError::InvalidInput(format!(
+            "Subtitle track {} is stored as images ({}) and can't be converted to text",
+            track_index, track.codec.as_deref().unwrap_or_default(),
+        )));
+    }
+
+    let mut command = tokio::process::Command::new(ffmpeg_paths::ffmpeg());
+    command
+        .arg("-i")
+        .arg(&video_path)
+        .args(["-map", &format!("0:{}", track_index), "-c:s", encoder, "-y"])
+        .arg(&out_path);
+    // Subtitle packets are spread over the whole file, so all of it is demuxed.
+    let output = output_with_timeout(&mut command, "ffmpeg", full_decode_timeout(info.duration)).await?;
+    if !output.status.success() {
+        return Err(AppError::FfmpegFailed(ffmpeg_failure("Subtitle extraction failed", &String::from_utf8_lossy(&output.stderr))));
+    }
+    Ok(out_path)
+}
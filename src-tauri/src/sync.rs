@@ -0,0 +1,145 @@
+// Manifest of what needs copying to bring a portable copy of (part of) the library up to date.
+//
+// The target mirrors the library as `target_dir/<folder_name>/<file_name>`. Only the manifest is
+// computed here; the copying itself is left to the caller.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::State;
+use walkdir::WalkDir;
+
+use crate::database::{self, Database, VideoFilter};
+use crate::error::AppError;
+use crate::{content_hash, VideoMetadata};
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SyncEntry {
+    /// The library file; `None` for files in the target that are no longer in the selection.
+    pub source_path: Option<String>,
+    pub target_path: String,
+    /// Size of the source file, or of the target file for deletions.
+    pub size: u64,
+}
+
+/// Selected videos that would all be copied to the same `target_path`, e.g. from two library
+/// folders with the same name. They are left out of the other lists.
+#[derive(Debug, Serialize, Clone)]
+pub struct SyncCollision {
+    pub target_path: String,
+    pub source_paths: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct SyncManifest {
+    /// Selected videos missing from the target.
+    pub to_copy: Vec<SyncEntry>,
+    /// Videos present in the target whose contents differ from the library file.
+    pub to_update: Vec<SyncEntry>,
+    /// Videos in the target that aren't part of the selection.
+    pub to_delete: Vec<SyncEntry>,
+    pub up_to_date: Vec<SyncEntry>,
+    pub collisions: Vec<SyncCollision>,
+}
+
+fn modified(metadata: &std::fs::Metadata) -> SystemTime {
+    metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+/// Whether the copy at `target_path` matches `video`. With a stored `content_hash` the copy's
+/// contents are hashed and compared; otherwise it has to have the same size and not be older
+/// than the source, which holds for copies made with timestamps preserved as well as fresh ones.
+fn is_up_to_date(video: &VideoMetadata, target_path: &Path, target: &std::fs::Metadata) -> bool {
+    if target.len() != video.file_size {
+        return false;
+    }
+    if let Some(content_hash) = &video.content_hash {
+        return content_hash::full_hash(target_path).is_ok_and(|hash| &hash == content_hash);
+    }
+    let source_modified = std::fs::metadata(Path::new(&video.full_path))
+        .map(|m| modified(&m))
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    modified(target) >= source_modified
+}
+
+/// Compares the stored videos matching `filter` (all videos without one) against the copies in
+/// `target_dir`, see `is_up_to_date`. Runs on a blocking thread, as it may hash every copy.
+#[tauri::command]
+pub async fn sync_manifest(db: State<'_, Database>, target_dir: String, filter: Option<VideoFilter>) -> Result<SyncManifest, AppError> {
+    let (clause, values) = filter.unwrap_or_default().to_sql();
+    let videos = database::query_videos(
+        &db.conn(),
+        &format!("{} ORDER BY folder_name, file_name", clause),
+        rusqlite::params_from_iter(values),
+    )?;
+
+    let target_dir = PathBuf::from(target_dir);
+    if !target_dir.is_dir() {
+        return Err(AppError::InvalidInput(format!("{} is not a directory", target_dir.display())));
+    }
+
+    Ok(tauri::async_runtime::spawn_blocking(move || build_manifest(&target_dir, videos))
+        .await
+        .map_err(|e| e.to_string())?)
+}
+
+fn build_manifest(target_dir: &Path, videos: Vec<VideoMetadata>) -> SyncManifest {
+    // Video files currently in the target, keyed by path.
+    let mut existing: HashMap<PathBuf, std::fs::Metadata> = WalkDir::new(target_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_file() && crate::is_video_file(entry.path()))
+        .filter_map(|entry| Some((entry.path().to_path_buf(), entry.metadata().ok()?)))
+        .collect();
+
+    // Grouped by target path, keeping the query's order.
+    let mut targets: Vec<(PathBuf, Vec<VideoMetadata>)> = Vec::new();
+    let mut target_index: HashMap<PathBuf, usize> = HashMap::new();
+    for video in videos {
+        let target_path = target_dir.join(&video.folder_name).join(&video.file_name);
+        match target_index.get(&target_path) {
+            Some(&index) => targets[index].1.push(video),
+            None => {
+                target_index.insert(target_path.clone(), targets.len());
+                targets.push((target_path, vec![video]));
+            }
+        }
+    }
+
+    let mut manifest = SyncManifest::default();
+    for (target_path, mut sources) in targets {
+        let target = existing.remove(&target_path);
+        if sources.len() > 1 {
+            manifest.collisions.push(SyncCollision {
+                target_path: target_path.to_string_lossy().to_string(),
+                source_paths: sources.into_iter().map(|video| video.full_path).collect(),
+            });
+            continue;
+        }
+        let video = sources.remove(0);
+        let entry = SyncEntry {
+            source_path: Some(video.full_path.clone()),
+            target_path: target_path.to_string_lossy().to_string(),
+            size: video.file_size,
+        };
+
+        match target {
+            None => manifest.to_copy.push(entry),
+            Some(target) if is_up_to_date(&video, &target_path, &target) => manifest.up_to_date.push(entry),
+            Some(_) => manifest.to_update.push(entry),
+        }
+    }
+
+    let mut leftovers: Vec<_> = existing.into_iter().collect();
+    leftovers.sort_by(|a, b| a.0.cmp(&b.0));
+    manifest.to_delete = leftovers.into_iter()
+        .map(|(path, metadata)| SyncEntry {
+            source_path: None,
+            target_path: path.to_string_lossy().to_string(),
+            size: metadata.len(),
+        })
+        .collect();
+
+    manifest
+}
@@ -0,0 +1,174 @@
+// Free-form tags on videos, independent of the folder structure.
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+use crate::VideoMetadata;
+
+/// Normalizes `tag`, rejecting tags that are empty once trimmed.
+fn checked_tag(tag: &str) -> Result<String, AppError> {
+    let tag = database::normalize_tag(tag);
+    if tag.is_empty() {
+        return Err(AppError::InvalidInput("Tag must not be empty".to_string()));
+    }
+    Ok(tag)
+}
+
+fn tags_of(conn: &rusqlite::Connection, full_path: &str) -> Result<Vec<String>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT tags.name FROM video_tags JOIN tags ON tags.id = video_tags.tag_id
+         WHERE video_tags.video_path = ?1 ORDER BY tags.name",
+    )?;
+    let rows = stmt.query_map([full_path], |row| row.get(0))?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// Tags `video_id` with `tag` (normalized, see `database::normalize_tag`). Returns the video's tags.
+#[tauri::command]
+pub async fn add_tag(db: State<'_, Database>, video_id: String, tag: String) -> Result<Vec<String>, AppError> {
+    let tag = checked_tag(&tag)?;
+    let conn = db.conn();
+    let video = database::video_by_id(&conn, &video_id)?;
+    database::tag_video(&conn, &video.full_path, &tag)?;
+    tags_of(&conn, &video.full_path)
+}
+
+/// Removes `tag` from `video_id`; a tag no video has anymore is deleted. Returns the video's tags.
+#[tauri::command]
+pub async fn remove_tag(db: State<'_, Database>, video_id: String, tag: String) -> Result<Vec<String>, AppError> {
+    let tag = database::normalize_tag(&tag);
+    let conn = db.conn();
+    let video = database::video_by_id(&conn, &video_id)?;
+    conn.execute(
+        "DELETE FROM video_tags WHERE video_path = ?1 AND tag_id = (SELECT id FROM tags WHERE name = ?2)",
+        [&video.full_path, &tag],
+    )?;
+    conn.execute(
+        "DELETE FROM tags WHERE name = ?1 AND NOT EXISTS (SELECT 1 FROM video_tags WHERE tag_id = tags.id)",
+        [&tag],
+    )?;
+    tags_of(&conn, &video.full_path)
+}
+
+#[tauri::command]
+pub async fn get_tags(db: State<'_, Database>, video_id: String) -> Result<Vec<String>, AppError> {
+    let conn = db.conn();
+    let video = database::video_by_id(&conn, &video_id)?;
+    tags_of(&conn, &video.full_path)
+}
+
+/// Every tag in use, alphabetically.
+#[tauri::command]
+pub async fn list_all_tags(db: State<'_, Database>) -> Result<Vec<String>, AppError> {
+    let conn = db.conn();
+    let mut stmt = conn.prepare(
+        "SELECT name FROM tags WHERE EXISTS (SELECT 1 FROM video_tags WHERE tag_id = tags.id) ORDER BY name",
+    )?;
+    let rows = stmt.query_map([], |row| row.get(0))?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+#[tauri::command]
+pub async fn get_videos_by_tag(db: State<'_, Database>, tag: String) -> Result<Vec<VideoMetadata>, AppError> {
+    database::query_videos(
+        &db.conn(),
+        "WHERE full_path IN (
+            SELECT video_tags.video_path FROM video_tags JOIN tags ON tags.id = video_tags.tag_id
+            WHERE tags.name = ?1
+         ) ORDER BY folder_name, file_name",
+        [database::normalize_tag(&tag)],
+    )
+}
+
+/// Header cells recognized in the first column of a tag CSV; such a first row is skipped.
+const CSV_IDENTIFIER_HEADERS: &[&str] = &["path", "full_path", "file", "file_name", "filename", "identifier"];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct UnmatchedTagRow {
+    /// 1-based line number in the CSV file.
+    pub line: u64,
+    pub identifier: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TagImportReport {
+    pub rows_processed: u64,
+    pub videos_tagged: u64,
+    /// Number of new video/tag links; tags a video already had aren't counted.
+    pub tags_applied: u64,
+    pub unmatched: Vec<UnmatchedTagRow>,
+}
+
+/// Applies tags from a CSV file in one transaction. Each row is `identifier, tag, tag, ...` where
+/// the identifier is either a full path or a bare file name (applying to every video with that
+/// name). A cell may also hold several tags separated by `;`. Rows matching no video are reported
+/// back instead of failing the import.
+#[tauri::command]
+pub async fn import_tags_csv(db: State<'_, Database>, csv_path: String) -> Result<TagImportReport, AppError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_path(&csv_path)
+        .map_err(|e| format!("Failed to open CSV: {}", e))?;
+
+    let mut conn = db.conn();
+    let tx = conn.transaction()?;
+
+    let mut report = TagImportReport {
+        rows_processed: 0,
+        videos_tagged: 0,
+        tags_applied: 0,
+        unmatched: Vec::new(),
+    };
+
+    for (index, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| format!("Invalid CSV: {}", e))?;
+        let line = record.position().map(|p| p.line()).unwrap_or(index as u64 + 1);
+        let Some(identifier) = record.get(0).filter(|id| !id.is_empty()) else {
+            continue;
+        };
+        if index == 0 && CSV_IDENTIFIER_HEADERS.contains(&identifier.to_lowercase().as_str()) {
+            continue;
+        }
+
+        let tags: Vec<String> = record.iter()
+            .skip(1)
+            .flat_map(|cell| cell.split(';'))
+            .map(database::normalize_tag)
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        report.rows_processed += 1;
+
+        // An exact path wins; otherwise the identifier is taken as a file name.
+        let mut paths: Vec<String> = {
+            let mut stmt = tx.prepare("SELECT full_path FROM videos WHERE full_path = ?1")?;
+            let rows = stmt.query_map([database::normalize_path(identifier)], |row| row.get(0))?;
+            rows.collect::<Result<_, _>>()?
+        };
+        if paths.is_empty() {
+            let mut stmt = tx.prepare("SELECT full_path FROM videos WHERE file_name = ?1")?;
+            let rows = stmt.query_map([identifier], |row| row.get(0))?;
+            paths = rows.collect::<Result<_, _>>()?;
+        }
+
+        if paths.is_empty() {
+            report.unmatched.push(UnmatchedTagRow { line, identifier: identifier.to_string() });
+            continue;
+        }
+
+        for path in &paths {
+            for tag in &tags {
+                if database::tag_video(&tx, path, tag)? {
+                    report.tags_applied += 1;
+                }
+            }
+        }
+        report.videos_tagged += paths.len() as u64;
+    }
+
+    tx.commit()?;
+    Ok(report)
+}
@@ -0,0 +1,155 @@
+// Size limit for the generated thumbnail directory, evicting least recently used files.
+//
+// Reads through `get_thumbnail_data` are recorded in the `thumbnail_access` table; files never
+// read fall back to their modification time. Evicted thumbnails are unlinked from their video
+// so they get regenerated the next time they're needed.
+
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::time::SystemTime;
+use tauri::{AppHandle, Manager, State};
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+
+/// Settings key holding the cache limit in bytes.
+const THUMBNAIL_CACHE_LIMIT_KEY: &str = "max_thumbnail_cache_bytes";
+
+/// Limit used until one is configured (1 GiB).
+const DEFAULT_THUMBNAIL_CACHE_LIMIT: u64 = 1 << 30;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ThumbnailCacheSize {
+    pub total_bytes: u64,
+    pub file_count: u64,
+    pub limit_bytes: u64,
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+fn cache_limit(conn: &Connection) -> u64 {
+    database::get_setting(conn, THUMBNAIL_CACHE_LIMIT_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or(DEFAULT_THUMBNAIL_CACHE_LIMIT)
+}
+
+/// Records that the thumbnail at `path` was just used.
+pub fn touch(conn: &Connection, path: &str) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT INTO thumbnail_access (path, accessed_at) VALUES (?1, ?2)
+         ON CONFLICT(path) DO UPDATE SET accessed_at = excluded.accessed_at",
+        rusqlite::params![database::normalize_path(path), now_millis()],
+    )?;
+    Ok(())
+}
+
+/// Files in the thumbnail directory as `(normalized path, size, last use in epoch millis)`.
+fn cached_files(conn: &Connection) -> Result<Vec<(String, u64, i64)>, AppError> {
+    let accessed: HashMap<String, i64> = {
+        let mut stmt = conn.prepare("SELECT path, accessed_at FROM thumbnail_access")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(crate::thumbnails_dir()?)?.flatten() {
+        let Ok(metadata) = entry.metadata() else { continue };
+        if !metadata.is_file() {
+            continue;
+        }
+        let path = database::normalize_path(&entry.path().to_string_lossy());
+        let modified = metadata.modified()
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64;
+        let last_used = accessed.get(&path).copied().unwrap_or(modified).max(modified);
+        files.push((path, metadata.len(), last_used));
+    }
+    Ok(files)
+}
+
+/// Deletes least recently used thumbnails until the cache fits its limit. Returns the number
+/// of bytes freed.
+pub fn enforce_limit(conn: &Connection) -> Result<u64, AppError> {
+    let limit = cache_limit(conn);
+    let mut files = cached_files(conn)?;
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    if total <= limit {
+        return Ok(0);
+    }
+
+    files.sort_by_key(|(_, _, last_used)| *last_used);
+    let mut freed = 0;
+    for (path, size, _) in files {
+        if total <= limit {
+            break;
+        }
+        if fs::remove_file(&path).is_err() {
+            continue;
+        }
+        total -= size;
+        freed += size;
+        conn.execute("UPDATE videos SET thumbnail_path = NULL WHERE thumbnail_path = ?1", [&path])?;
+        conn.execute("UPDATE videos SET motion_thumbnail_path = NULL WHERE motion_thumbnail_path = ?1", [&path])?;
+        conn.execute("DELETE FROM thumbnail_access WHERE path = ?1", [&path])?;
+    }
+    Ok(freed)
+}
+
+/// Trims the cache in the background, e.g. after startup or generating thumbnails. Failures
+/// are only logged.
+pub fn enforce_limit_in_background(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = enforce_limit(&app.state::<Database>().conn()) {
+            log::error!("Error trimming the thumbnail cache: {}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_thumbnail_cache_size(db: State<'_, Database>) -> Result<ThumbnailCacheSize, AppError> {
+    let conn = db.conn();
+    let files = cached_files(&conn)?;
+    Ok(ThumbnailCacheSize {
+        total_bytes: files.iter().map(|(_, size, _)| size).sum(),
+        file_count: files.len() as u64,
+        limit_bytes: cache_limit(&conn),
+    })
+}
+
+/// Sets the cache limit and trims the cache to it right away. Returns the number of bytes freed.
+#[tauri::command]
+pub async fn set_thumbnail_cache_limit(db: State<'_, Database>, bytes: u64) -> Result<u64, AppError> {
+    let conn = db.conn();
+    database::set_setting(&conn, THUMBNAIL_CACHE_LIMIT_KEY, &bytes)?;
+    enforce_limit(&conn)
+}
+
+/// Deletes every generated image (thumbnails, motion thumbnails, waveforms) and unlinks them from
+/// their videos. Returns the number of bytes freed.
+#[tauri::command]
+pub async fn clear_thumbnail_cache(db: State<'_, Database>) -> Result<u64, AppError> {
+    let conn = db.conn();
+    let mut freed = 0;
+    for (path, size, _) in cached_files(&conn)? {
+        if fs::remove_file(&path).is_ok() {
+            freed += size;
+        }
+    }
+    conn.execute_batch(
+        "UPDATE videos SET thumbnail_path = NULL, motion_thumbnail_path = NULL
+         WHERE thumbnail_path IS NOT NULL OR motion_thumbnail_path IS NOT NULL;
+         DELETE FROM thumbnail_access;"
+    )?;
+    Ok(freed)
+}
@@ -0,0 +1,213 @@
+// Queue in front of the transcodes, so starting many at once doesn't spawn an ffmpeg per file.
+// Jobs start in the order they were queued, at most `transcode_concurrency` at a time.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::watch;
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+
+/// Settings key holding the number of transcodes allowed to run at the same time.
+pub const TRANSCODE_CONCURRENCY_KEY: &str = "transcode_concurrency";
+
+/// A single x264/x265 encode already keeps most cores busy.
+const DEFAULT_TRANSCODE_CONCURRENCY: u32 = 1;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct QueuedTranscode {
+    pub job_id: String,
+    pub input_path: String,
+}
+
+/// Payload of the "transcode-queued", "transcode-started" and "transcode-finished" events.
+#[derive(Debug, Serialize, Clone)]
+pub struct TranscodeQueueEvent {
+    pub job_id: String,
+    pub input_path: String,
+    /// 1-based place among the waiting jobs; "transcode-queued" is sent again whenever it
+    /// changes. `None` once the job has started.
+    pub position: Option<usize>,
+    /// Set on "transcode-finished" when the transcode failed or was cancelled.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct TranscodeQueueState {
+    pub running: Vec<QueuedTranscode>,
+    /// In the order they will start.
+    pub pending: Vec<QueuedTranscode>,
+}
+
+#[derive(Default)]
+struct Jobs {
+    running: Vec<QueuedTranscode>,
+    pending: VecDeque<QueuedTranscode>,
+}
+
+pub struct TranscodeQueue {
+    jobs: Mutex<Jobs>,
+    /// Bumped on every change to `jobs`, waking the jobs waiting for their turn.
+    changed: watch::Sender<u64>,
+}
+
+impl Default for TranscodeQueue {
+    fn default() -> Self {
+        TranscodeQueue { jobs: Mutex::default(), changed: watch::Sender::new(0) }
+    }
+}
+
+impl TranscodeQueue {
+    fn jobs(&self) -> MutexGuard<'_, Jobs> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn notify_changed(&self) {
+        self.changed.send_modify(|generation| *generation = generation.wrapping_add(1));
+    }
+
+    /// Takes `job_id` out of the queue if it hasn't started yet. Returns whether it was waiting.
+    pub fn dequeue(&self, job_id: &str) -> bool {
+        let removed = {
+            let mut jobs = self.jobs();
+            let before = jobs.pending.len();
+            jobs.pending.retain(|job| job.job_id != job_id);
+            jobs.pending.len() != before
+        };
+        if removed {
+            self.notify_changed();
+        }
+        removed
+    }
+}
+
+/// A started job's place among the running ones, given back when dropped.
+pub struct TranscodeSlot {
+    app: AppHandle,
+    job_id: String,
+}
+
+impl Drop for TranscodeSlot {
+    fn drop(&mut self) {
+        let queue = self.app.state::<TranscodeQueue>();
+        {
+            let mut jobs = queue.jobs();
+            if let Some(index) = jobs.running.iter().position(|job| job.job_id == self.job_id) {
+                jobs.running.remove(index);
+            }
+        }
+        queue.notify_changed();
+    }
+}
+
+fn concurrency(app: &AppHandle) -> usize {
+    database::get_setting(&app.state::<Database>().conn(), TRANSCODE_CONCURRENCY_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or(DEFAULT_TRANSCODE_CONCURRENCY)
+        .max(1) as usize
+}
+
+fn emit(app: &AppHandle, event: &str, job_id: &str, input_path: &str, position: Option<usize>, error: Option<String>) {
+    let _ = app.emit(event, TranscodeQueueEvent {
+        job_id: job_id.to_string(),
+        input_path: input_path.to_string(),
+        position,
+        error,
+    });
+}
+
+/// Takes a job out of the waiting line if the wait ends without it starting, e.g. when the
+/// waiting future is dropped.
+struct Waiting<'a> {
+    queue: &'a TranscodeQueue,
+    job_id: &'a str,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.queue.dequeue(self.job_id);
+    }
+}
+
+/// Queues `job_id` and waits until it may start. Fails with `Cancelled` when the job is
+/// dequeued through `cancel_transcode` while waiting; `cancelled_message` is its message.
+pub async fn wait_for_slot(app: &AppHandle, job_id: &str, input_path: &str, cancelled_message: &str) -> Result<TranscodeSlot, AppError> {
+    let queue = app.state::<TranscodeQueue>();
+    queue.jobs().pending.push_back(QueuedTranscode {
+        job_id: job_id.to_string(),
+        input_path: input_path.to_string(),
+    });
+    queue.notify_changed();
+    let waiting = Waiting { queue: &queue, job_id };
+
+    let mut reported = None;
+    loop {
+        // Subscribing before looking at the queue means no change in between goes unnoticed.
+        let mut changed = queue.changed.subscribe();
+        let limit = concurrency(app);
+        let position = {
+            let mut jobs = queue.jobs();
+            let Some(index) = jobs.pending.iter().position(|job| job.job_id == job_id) else {
+                drop(jobs);
+                emit(app, "transcode-finished", job_id, input_path, None, Some(cancelled_message.to_string()));
+                return Err(AppError::Cancelled(cancelled_message.to_string()));
+            };
+            if index == 0 && jobs.running.len() < limit {
+                if let Some(job) = jobs.pending.pop_front() {
+                    jobs.running.push(job);
+                }
+                None
+            } else {
+                Some(index + 1)
+            }
+        };
+        let Some(position) = position else {
+            // Started, so there is nothing left to take out of the waiting line.
+            std::mem::forget(waiting);
+            queue.notify_changed();
+            emit(app, "transcode-started", job_id, input_path, None, None);
+            return Ok(TranscodeSlot { app: app.clone(), job_id: job_id.to_string() });
+        };
+        if reported != Some(position) {
+            emit(app, "transcode-queued", job_id, input_path, Some(position), None);
+            reported = Some(position);
+        }
+        // The sender lives in app state for as long as the app, so this only returns on a change.
+        let _ = changed.changed().await;
+    }
+}
+
+/// Sends "transcode-finished" for a job that held a slot.
+pub fn report_finished<T>(app: &AppHandle, job_id: &str, input_path: &str, result: &Result<T, AppError>) {
+    let error = result.as_ref().err().map(|e| e.to_string());
+    emit(app, "transcode-finished", job_id, input_path, None, error);
+}
+
+#[tauri::command]
+pub async fn get_transcode_queue(queue: State<'_, TranscodeQueue>) -> Result<TranscodeQueueState, AppError> {
+    let jobs = queue.jobs();
+    Ok(TranscodeQueueState {
+        running: jobs.running.clone(),
+        pending: jobs.pending.iter().cloned().collect(),
+    })
+}
+
+#[tauri::command]
+pub async fn get_transcode_concurrency(app: AppHandle) -> Result<u32, AppError> {
+    Ok(concurrency(&app) as u32)
+}
+
+/// Sets how many transcodes may run at once. Raising it starts waiting jobs right away; running
+/// jobs are never stopped by lowering it.
+#[tauri::command]
+pub async fn set_transcode_concurrency(db: State<'_, Database>, queue: State<'_, TranscodeQueue>, concurrency: u32) -> Result<(), AppError> {
+    if concurrency == 0 {
+        return Err(AppError::InvalidInput("At least one transcode must be allowed to run".to_string()));
+    }
+    database::set_setting(&db.conn(), TRANSCODE_CONCURRENCY_KEY, &concurrency)?;
+    queue.notify_changed();
+    Ok(())
+}
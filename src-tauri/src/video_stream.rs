@@ -0,0 +1,204 @@
+// Serves indexed video files to `<video src>` with HTTP range support, so the webview can seek
+// without the file being read into memory or passed through IPC. Two transports share the
+// range handling:
+//
+// - The `stream` URI scheme: `stream://localhost/<percent-encoded full path>` on macOS and Linux,
+//   `http://stream.localhost/<percent-encoded full path>` on Windows, which is what the
+//   frontend's `convertFileSrc(path, "stream")` produces.
+// - A localhost HTTP server started with the app, for players that need plain http URLs. Its
+//   URLs come from `get_stream_url` and carry a per-run token so other local software can't use it.
+//
+// Only files in the library are served. Each response carries at most `MAX_RESPONSE_BYTES`;
+// players request the rest as they go.
+
+use http_body_util::Full;
+use hyper::body::Bytes;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use std::convert::Infallible;
+use std::fs::File;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{AppHandle, Manager, State, UriSchemeContext, UriSchemeResponder};
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+
+/// Name of the URI scheme.
+pub const SCHEME: &str = "stream";
+
+/// Largest body sent for one request (8 MiB), also the most `read_file_range` returns at once.
+pub const MAX_RESPONSE_BYTES: u64 = 8 << 20;
+
+/// Protocol handler registered for `SCHEME`. Files are read off the webview's thread.
+pub fn handle(ctx: UriSchemeContext<'_, tauri::Wry>, request: Request<Vec<u8>>, responder: UriSchemeResponder) {
+    let app = ctx.app_handle().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let range = request.headers().get(header::RANGE).and_then(|value| value.to_str().ok());
+        responder.respond(serve_path(&app, request.uri().path(), range));
+    });
+}
+
+fn error_response(status: StatusCode, message: String) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain")
+        .body(message.into_bytes())
+        .unwrap_or_default()
+}
+
+/// Answers a request for the URL path `/<percent-encoded full path>`.
+fn serve_path(app: &AppHandle, url_path: &str, range: Option<&str>) -> Response<Vec<u8>> {
+    let encoded = url_path.trim_start_matches('/');
+    let Ok(full_path) = percent_decode_str(encoded).decode_utf8() else {
+        return error_response(StatusCode::BAD_REQUEST, "Invalid path".to_string());
+    };
+    serve(app, &full_path, range).unwrap_or_else(|(status, message)| error_response(status, message))
+}
+
+/// Whether `full_path` is a video in the library.
+pub(crate) fn is_indexed(app: &AppHandle, full_path: &str) -> bool {
+    app.state::<Database>().conn()
+        .query_row(
+            "SELECT 1 FROM videos WHERE full_path = ?1",
+            [database::normalize_path(full_path)],
+            |_| Ok(()),
+        )
+        .is_ok()
+}
+
+/// Content type for the webview, from the file extension.
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().map(|e| e.to_string_lossy().to_lowercase()).as_deref() {
+        Some("mp4" | "m4v") => "video/mp4",
+        Some("webm") => "video/webm",
+        Some("mkv") => "video/x-matroska",
+        Some("mov") => "video/quicktime",
+        Some("avi") => "video/x-msvideo",
+        Some("ts" | "mts" | "m2ts") => "video/mp2t",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Parses a `Range: bytes=...` header into an inclusive byte range of a `size`-byte file.
+/// Only single ranges are supported; `None` means the range can't be satisfied.
+fn parse_range(range: &str, size: u64) -> Option<(u64, u64)> {
+    let spec = range.trim().strip_prefix("bytes=")?.split(',').next()?.trim();
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        // `bytes=-500` is the last 500 bytes.
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            (size.checked_sub(suffix.min(size))?, size.checked_sub(1)?)
+        }
+        (start, "") => (start.parse().ok()?, size.checked_sub(1)?),
+        (start, end) => (start.parse().ok()?, end.parse::<u64>().ok()?.min(size.checked_sub(1)?)),
+    };
+    (start <= end && start < size).then_some((start, end))
+}
+
+fn serve(app: &AppHandle, full_path: &str, range: Option<&str>) -> Result<Response<Vec<u8>>, (StatusCode, String)> {
+    // Only files in the library are served, so neither transport can read arbitrary files.
+    if !is_indexed(app, full_path) {
+        return Err((StatusCode::FORBIDDEN, format!("Not an indexed video: {}", full_path)));
+    }
+
+    let mut file = File::open(full_path).map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+    let size = file.metadata().map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?.len();
+
+    // Players always send a range; a request without one is answered like `bytes=0-`.
+    let Some((start, end)) = parse_range(range.unwrap_or("bytes=0-"), size) else {
+        return Response::builder()
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", size))
+            .body(Vec::new())
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
+    };
+    let end = end.min(start + MAX_RESPONSE_BYTES - 1);
+
+    let mut body = vec![0u8; (end - start + 1) as usize];
+    file.seek(SeekFrom::Start(start))
+        .and_then(|_| file.read_exact(&mut body))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Response::builder()
+        .status(StatusCode::PARTIAL_CONTENT)
+        .header(header::CONTENT_TYPE, content_type(Path::new(full_path)))
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, size))
+        .header(header::CONTENT_LENGTH, body.len())
+        .body(body)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Address of the localhost HTTP server.
+pub struct StreamServer {
+    port: u16,
+    /// First path segment of every URL, random per run.
+    token: String,
+}
+
+fn random_token() -> String {
+    let random = || std::collections::hash_map::RandomState::new().build_hasher().finish();
+    format!("{:016x}{:016x}", random(), random())
+}
+
+/// Binds the HTTP server to a free port on 127.0.0.1 and starts serving in the background.
+pub fn start_server(app: &AppHandle) -> Result<StreamServer, AppError> {
+    let listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+    listener.set_nonblocking(true)?;
+    let port = listener.local_addr()?.port();
+    let token = random_token();
+
+    let app = app.clone();
+    let prefix = format!("/{}/", token);
+    tauri::async_runtime::spawn(async move {
+        let Ok(listener) = tokio::net::TcpListener::from_std(listener) else { return };
+        loop {
+            let Ok((stream, _)) = listener.accept().await else { continue };
+            let (app, prefix) = (app.clone(), prefix.clone());
+            tauri::async_runtime::spawn(async move {
+                let service = hyper::service::service_fn(move |request: hyper::Request<hyper::body::Incoming>| {
+                    let (app, prefix) = (app.clone(), prefix.clone());
+                    async move {
+                        let path = request.uri().path().to_string();
+                        let range = request.headers().get(header::RANGE)
+                            .and_then(|value| value.to_str().ok())
+                            .map(str::to_string);
+                        let response = match path.strip_prefix(&prefix) {
+                            Some(url_path) => {
+                                let url_path = url_path.to_string();
+                                tauri::async_runtime::spawn_blocking(move || serve_path(&app, &url_path, range.as_deref()))
+                                    .await
+                                    .unwrap_or_else(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+                            }
+                            None => error_response(StatusCode::NOT_FOUND, "Not found".to_string()),
+                        };
+                        Ok::<_, Infallible>(response.map(|body| Full::new(Bytes::from(body))))
+                    }
+                });
+                let _ = hyper::server::conn::http1::Builder::new()
+                    .serve_connection(hyper_util::rt::TokioIo::new(stream), service)
+                    .await;
+            });
+        }
+    });
+
+    Ok(StreamServer { port, token })
+}
+
+/// Returns an `http://127.0.0.1:<port>/...` URL for an indexed video that `<video src>` can
+/// seek in directly.
+#[tauri::command]
+pub async fn get_stream_url(app: AppHandle, server: State<'_, StreamServer>, video_path: String) -> Result<String, AppError> {
+    if !is_indexed(&app, &video_path) {
+        return Err(AppError::NotFound(format!("Not an indexed video: {}", video_path)));
+    }
+    Ok(format!(
+        "http://127.0.0.1:{}/{}/{}",
+        server.port,
+        server.token,
+        utf8_percent_encode(&video_path, NON_ALPHANUMERIC),
+    ))
+}
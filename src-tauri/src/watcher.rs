@@ -0,0 +1,343 @@
+// Live updates for watched directories: new, changed and deleted video files are reflected in
+// the library without a manual re-index.
+//
+// Local directories are watched through the OS's change notifications (notify, with events
+// debounced by notify-debouncer-full), and only the paths named in events are looked at again.
+// Network shares often don't deliver such notifications, so they, and directories the OS won't
+// watch, are polled every `WATCH_INTERVAL` instead by comparing the file sizes and modification
+// times of the whole tree. Either way a new or changed file is only probed once it looks the same
+// on two checks in a row, so a file that is still being written, or a temporary file an editor
+// creates and removes, doesn't trigger a probe on every write.
+
+use notify::event::{AccessKind, AccessMode, ModifyKind};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, RecommendedCache};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::mpsc;
+
+use crate::database::{self, Database};
+use crate::error::AppError;
+use crate::{playback, CrawlOptions};
+
+/// Time between two checks of a watched directory: of the paths named in events since the last
+/// one, or of the whole tree when polling.
+const WATCH_INTERVAL: Duration = Duration::from_secs(3);
+
+/// How long a path has to be quiet before notify-debouncer-full reports its events.
+const EVENT_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Stop flags of the running watchers, by normalized root path.
+#[derive(Default)]
+pub struct Watchers(Mutex<HashMap<String, Arc<AtomicBool>>>);
+
+impl Watchers {
+    fn flags(&self) -> MutexGuard<'_, HashMap<String, Arc<AtomicBool>>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Payload of the "library-changed" event, sent when a check changed the library.
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct LibraryChange {
+    pub root: String,
+    /// Full paths of the videos added to the library.
+    pub added: Vec<String>,
+    /// Full paths of the videos that were re-probed because the file changed.
+    pub modified: Vec<String>,
+    /// Full paths of the videos removed from the library because the file is gone.
+    pub removed: Vec<String>,
+    /// Files that could not be probed, with the error.
+    pub failed: Vec<(String, String)>,
+}
+
+/// Size and modification time, to tell whether a file changed between checks.
+type Signature = (u64, SystemTime);
+
+/// Video files by normalized path. Ordered so the files under a directory are one range.
+type Files = BTreeMap<PathBuf, Signature>;
+
+fn normalized(path: &Path) -> PathBuf {
+    PathBuf::from(database::normalize_path(&path.to_string_lossy()))
+}
+
+/// The video files at or under `path`, which may be a single file. Fails if `path` itself
+/// can't be read; unreadable entries below it are left out.
+fn snapshot(path: &Path) -> Result<Files, AppError> {
+    let options = CrawlOptions::default();
+    let mut files = Files::new();
+    for entry in crate::crawl_walker(&path.to_string_lossy(), &options)? {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) if e.depth() == 0 => return Err(std::io::Error::from(e).into()),
+            Err(_) => continue,
+        };
+        if !options.selects(&entry) {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else { continue };
+        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        files.insert(normalized(entry.path()), (metadata.len(), modified));
+    }
+    Ok(files)
+}
+
+async fn take_snapshot(path: &Path) -> Result<Files, AppError> {
+    let path = path.to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || snapshot(&path)).await.map_err(|e| e.to_string())?
+}
+
+/// What is at or under each of `paths` now; an empty map for a path that no longer exists.
+/// `None` for a path that couldn't be read, and for a directory whose flag isn't set, e.g. when
+/// only its timestamps changed.
+fn snapshot_paths(paths: Vec<(PathBuf, bool)>) -> Vec<(PathBuf, Option<Files>)> {
+    paths.into_iter()
+        .map(|(path, walk_directory)| {
+            let files = match std::fs::symlink_metadata(&path) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Some(Files::new()),
+                Err(_) => None,
+                Ok(metadata) if metadata.is_dir() && !walk_directory => None,
+                Ok(_) => snapshot(&path).ok(),
+            };
+            (path, files)
+        })
+        .collect()
+}
+
+/// Starts the OS's change notifications for `root`, or `None` if they aren't available.
+fn start_notifications(root: &Path, events: mpsc::UnboundedSender<DebounceEventResult>) -> Option<Debouncer<RecommendedWatcher, RecommendedCache>> {
+    let started = new_debouncer(EVENT_DEBOUNCE, None, move |result| {
+        let _ = events.send(result);
+    }).and_then(|mut debouncer| {
+        debouncer.watch(root, RecursiveMode::Recursive)?;
+        Ok(debouncer)
+    });
+    match started {
+        Ok(debouncer) => Some(debouncer),
+        Err(e) => {
+            log::warn!("Can't watch {} for changes, polling it instead: {}", root.display(), e);
+            None
+        }
+    }
+}
+
+struct WatchState {
+    /// The directory as given to `watch_directory`, reported in "library-changed" events.
+    path: String,
+    /// Its canonical, normalized spelling, the one event paths and walks use.
+    root: PathBuf,
+    /// Files as they were when last indexed (or when watching started).
+    settled: Files,
+    /// Files seen changed on the last check, with the signature they had then.
+    pending: Files,
+}
+
+impl WatchState {
+    /// The files under `scope` as `files` says they are now, plus every file under `scope` the
+    /// watcher knew of that isn't there any more (as `None`).
+    fn observed_in(&self, scope: &Path, files: Files) -> Vec<(PathBuf, Option<Signature>)> {
+        let under_scope = |known: &Files| -> Vec<PathBuf> {
+            known.range(scope.to_path_buf()..)
+                .take_while(|(path, _)| path.starts_with(scope))
+                .filter(|(path, _)| !files.contains_key(*path))
+                .map(|(path, _)| path.clone())
+                .collect()
+        };
+        let mut observed: Vec<(PathBuf, Option<Signature>)> = under_scope(&self.settled).into_iter()
+            .chain(under_scope(&self.pending))
+            .map(|path| (path, None))
+            .collect();
+        observed.extend(files.into_iter().map(|(path, signature)| (path, Some(signature))));
+        observed
+    }
+
+    /// Brings the library in line with `observed` (`None` for files that are gone) and returns
+    /// what changed.
+    async fn update(&mut self, app: &AppHandle, observed: Vec<(PathBuf, Option<Signature>)>) -> LibraryChange {
+        let mut change = LibraryChange { root: self.path.clone(), ..Default::default() };
+
+        let mut ready = Vec::new();
+        let mut gone = Vec::new();
+        for (path, signature) in observed {
+            let Some(signature) = signature else {
+                self.pending.remove(&path);
+                if self.settled.remove(&path).is_some() {
+                    gone.push(path);
+                }
+                continue;
+            };
+            if self.settled.get(&path) == Some(&signature) {
+                self.pending.remove(&path);
+            } else if self.pending.get(&path) == Some(&signature) {
+                ready.push((path, signature));
+            } else {
+                self.pending.insert(path, signature);
+            }
+        }
+
+        let capabilities = playback::playback_capabilities(&app.state::<Database>().conn());
+        for (path, signature) in ready {
+            self.pending.remove(&path);
+            let full_path = path.to_string_lossy().to_string();
+            match crate::extract_video_metadata(&path, crate::DEFAULT_PROBE_TIMEOUT).await {
+                Ok(mut video) => {
+                    video.web_ready = playback::web_ready(&video, &capabilities);
+                    if let Err(e) = database::upsert_video(&app.state::<Database>().conn(), &video) {
+                        change.failed.push((full_path, e.to_string()));
+                        continue;
+                    }
+                    if self.settled.contains_key(&path) {
+                        change.modified.push(full_path);
+                    } else {
+                        change.added.push(full_path);
+                    }
+                    self.settled.insert(path, signature);
+                }
+                // Not retried until the file changes again.
+                Err(e) => {
+                    change.failed.push((full_path, e.to_string()));
+                    self.settled.insert(path, signature);
+                }
+            }
+        }
+
+        for path in gone {
+            let full_path = path.to_string_lossy().to_string();
+            let deleted = app.state::<Database>().conn().execute(
+                "DELETE FROM videos WHERE full_path = ?1",
+                [database::normalize_path(&full_path)],
+            );
+            if matches!(deleted, Ok(n) if n > 0) {
+                change.removed.push(full_path);
+            }
+        }
+        change
+    }
+}
+
+/// Keeps the library in sync with `state.root` until `stop` is set, from change notifications
+/// or, for network paths and when those aren't available, by polling.
+async fn watch(app: AppHandle, mut state: WatchState, stop: Arc<AtomicBool>) {
+    let (sender, mut events) = mpsc::unbounded_channel();
+    // Dropping the debouncer ends the notifications, so it lives as long as the loop.
+    let debouncer = if crate::is_network_path(&state.root.to_string_lossy()) {
+        None
+    } else {
+        start_notifications(&state.root, sender)
+    };
+    let notified = debouncer.is_some();
+    // Paths named in events since the last check, with whether a directory among them has to
+    // be walked (it was created, moved in, or the events were lost).
+    let mut dirty: HashMap<PathBuf, bool> = HashMap::new();
+    let mut checks = tokio::time::interval_at(tokio::time::Instant::now() + WATCH_INTERVAL, WATCH_INTERVAL);
+
+    while !stop.load(Ordering::Relaxed) {
+        tokio::select! {
+            Some(result) = events.recv(), if notified => match result {
+                Ok(batch) => {
+                    for event in batch {
+                        if event.need_rescan() {
+                            dirty.insert(state.root.clone(), true);
+                            continue;
+                        }
+                        let walk_directory = match event.kind {
+                            EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(_)) => true,
+                            // Reads, e.g. while the video plays, change nothing.
+                            EventKind::Access(kind) if kind != AccessKind::Close(AccessMode::Write) => continue,
+                            _ => false,
+                        };
+                        for path in &event.paths {
+                            let walk = dirty.entry(normalized(path)).or_default();
+                            *walk |= walk_directory;
+                        }
+                    }
+                }
+                Err(errors) => {
+                    log::warn!("Missed changes under {}, checking all of it: {:?}", state.root.display(), errors);
+                    dirty.insert(state.root.clone(), true);
+                }
+            },
+            _ = checks.tick() => {
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                let observed = if notified {
+                    if dirty.is_empty() && state.pending.is_empty() {
+                        continue;
+                    }
+                    // Files still settling are checked again even without new events.
+                    let mut paths: Vec<(PathBuf, bool)> = dirty.drain().collect();
+                    paths.extend(state.pending.keys().map(|path| (path.clone(), false)));
+                    let Ok(snapshots) = tauri::async_runtime::spawn_blocking(move || snapshot_paths(paths)).await else { continue };
+                    snapshots.into_iter()
+                        .filter_map(|(scope, files)| Some(state.observed_in(&scope, files?)))
+                        .flatten()
+                        .collect()
+                } else {
+                    let Ok(current) = take_snapshot(&state.root).await else { continue };
+                    let root = state.root.clone();
+                    state.observed_in(&root, current)
+                };
+
+                let change = state.update(&app, observed).await;
+                if !(change.added.is_empty() && change.modified.is_empty() && change.removed.is_empty() && change.failed.is_empty()) {
+                    let _ = app.emit("library-changed", change);
+                }
+            }
+        }
+    }
+    drop(debouncer);
+}
+
+/// Starts keeping the library in sync with the video files under `path`, reporting each
+/// change as a "library-changed" event (`LibraryChange`). Files already there when watching
+/// starts are left alone, index them first. Watching a path twice is a no-op. Fails if the
+/// directory can't be read, and watching it can then be tried again.
+#[tauri::command]
+pub async fn watch_directory(app: AppHandle, watchers: State<'_, Watchers>, path: String) -> Result<(), AppError> {
+    if !std::path::Path::new(&path).is_dir() {
+        return Err(AppError::InvalidInput(format!("{} is not a directory", path)));
+    }
+    // Event paths are absolute and resolved, so the tree is walked under the same spelling.
+    let root = normalized(&std::fs::canonicalize(&path)?);
+
+    let key = database::normalize_path(&path);
+    let stop = Arc::new(AtomicBool::new(false));
+    {
+        let mut flags = watchers.flags();
+        if flags.contains_key(&key) {
+            return Ok(());
+        }
+        flags.insert(key.clone(), stop.clone());
+    }
+
+    // The baseline: files already present are assumed to be indexed.
+    let settled = match take_snapshot(&root).await {
+        Ok(settled) => settled,
+        Err(e) => {
+            let mut flags = watchers.flags();
+            if flags.get(&key).is_some_and(|flag| Arc::ptr_eq(flag, &stop)) {
+                flags.remove(&key);
+            }
+            return Err(e);
+        }
+    };
+    let state = WatchState { path, root, settled, pending: Files::new() };
+    tauri::async_runtime::spawn(watch(app.clone(), state, stop));
+    Ok(())
+}
+
+/// Stops the watcher started with `watch_directory(path)`.
+#[tauri::command]
+pub async fn stop_watching(watchers: State<'_, Watchers>, path: String) -> Result<(), AppError> {
+    let stop = watchers.flags()
+        .remove(&database::normalize_path(&path))
+        .ok_or_else(|| AppError::NotFound(format!("Not watching {}", path)))?;
+    stop.store(true, Ordering::Relaxed);
+    Ok(())
+}